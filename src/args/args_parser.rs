@@ -1,3 +1,4 @@
+use crate::scan::text_decoder::*;
 use getopts::*;
 use std::env;
 
@@ -19,6 +20,16 @@ pub enum DbType {
     Unknown,
     Oracle,
     MySQL,
+    PostgreSQL,
+    SQLServer,
+    DB2,
+    H2,
+    SQLite,
+    DM,
+    KingbaseES,
+    OceanBaseOracle,
+    OceanBaseMySQL,
+    GaussDB,
 }
 
 impl DbType {
@@ -26,16 +37,151 @@ impl DbType {
         match name {
             "oracle" => DbType::Oracle,
             "mysql" => DbType::MySQL,
+            "postgresql" => DbType::PostgreSQL,
+            "sqlserver" => DbType::SQLServer,
+            "db2" => DbType::DB2,
+            "h2" => DbType::H2,
+            "sqlite" => DbType::SQLite,
+            "dm" => DbType::DM,
+            "kingbase" => DbType::KingbaseES,
+            "oceanbase-oracle" => DbType::OceanBaseOracle,
+            "oceanbase-mysql" => DbType::OceanBaseMySQL,
+            "gaussdb" | "opengauss" => DbType::GaussDB,
             _ => DbType::Unknown,
         }
     }
 }
 
+pub enum OutputFormat {
+    Unknown,
+    Sql,
+    SQLite,
+    Html,
+    Markdown,
+}
+
+impl OutputFormat {
+    fn from(name: &str) -> Self {
+        match name {
+            "sql" => OutputFormat::Sql,
+            "sqlite" => OutputFormat::SQLite,
+            "html" => OutputFormat::Html,
+            "markdown" => OutputFormat::Markdown,
+            _ => OutputFormat::Unknown,
+        }
+    }
+}
+
+pub enum NormalizeType {
+    Unknown,
+    None,
+    Whitespace,
+    Full,
+}
+
+impl NormalizeType {
+    fn from(name: &str) -> Self {
+        match name {
+            "none" => NormalizeType::None,
+            "whitespace" => NormalizeType::Whitespace,
+            "full" => NormalizeType::Full,
+            _ => NormalizeType::Unknown,
+        }
+    }
+}
+
+pub enum PlaceholderType {
+    Unknown,
+    /// 跟随方言
+    Dialect,
+    Question,
+    Named,
+    ColonIndex,
+    DollarIndex,
+    AtIndex,
+    Keep,
+}
+
+impl PlaceholderType {
+    fn from(name: &str) -> Self {
+        match name {
+            "?" => PlaceholderType::Question,
+            ":name" => PlaceholderType::Named,
+            ":n" => PlaceholderType::ColonIndex,
+            "$n" => PlaceholderType::DollarIndex,
+            "@pn" => PlaceholderType::AtIndex,
+            "keep" => PlaceholderType::Keep,
+            "dialect" => PlaceholderType::Dialect,
+            _ => PlaceholderType::Unknown,
+        }
+    }
+}
+
+pub enum AuditType {
+    Unknown,
+    Disabled,
+    Injection,
+}
+
+impl AuditType {
+    fn from(name: &str) -> Self {
+        match name {
+            "injection" => AuditType::Injection,
+            _ => AuditType::Unknown,
+        }
+    }
+}
+
+pub enum ReportType {
+    Unknown,
+    Disabled,
+    Tables,
+}
+
+impl ReportType {
+    fn from(name: &str) -> Self {
+        match name {
+            "tables" => ReportType::Tables,
+            _ => ReportType::Unknown,
+        }
+    }
+}
+
 pub struct Args {
     pub mode: XBatisMode,
     pub db_type: DbType,
-    pub src_dir: String,
+    /// 源目录，可指定多个
+    pub src_dirs: Vec<String>,
+    /// 文件列表，`-` 表示标准输入，为空时不读取
+    pub files_from: String,
     pub output_dir: String,
+    pub format: OutputFormat,
+    pub normalize: NormalizeType,
+    pub placeholder: PlaceholderType,
+    pub audit: AuditType,
+    pub report: ReportType,
+    /// `mybatis-config.xml` / `SqlMapConfig.xml`，为空时扫描源目录
+    pub config_file: String,
+    /// 按 `Spring Boot` 的 `mybatis.mapper-locations` 收集映射文件
+    pub spring_boot: bool,
+    /// 扫描时包含的文件
+    pub includes: Vec<String>,
+    /// 扫描时排除的文件或目录
+    pub excludes: Vec<String>,
+    /// 是否使用默认排除的目录
+    pub default_excludes: bool,
+    /// 是否遵循 `.gitignore`
+    pub gitignore: bool,
+    /// 是否展开 `.jar` / `.war` / `.zip` 中的映射文件
+    pub archives: bool,
+    /// 扫描时是否跟随符号链接
+    pub follow_links: bool,
+    /// 读取 `xml` 时使用的编码，为空时按 `BOM` 或 `xml` 声明
+    pub encoding: String,
+    /// 存在解析失败的文件时以非零退出码结束
+    pub strict: bool,
+    /// 是否读取归档中的映射文件引用的外部实体
+    pub archive_entities: bool,
     pub fast_fail: bool,
     pub show_version: bool,
     opts: Options,
@@ -45,43 +191,94 @@ impl Args {
     fn new(
         mode: XBatisMode,
         db_type: DbType,
-        src_dir: &String,
-        output_dir: &String,
+        src_dirs: &[String],
+        output_dir: &str,
         opts: Options,
     ) -> Self {
-        return Args {
+        Args {
             mode,
             db_type,
-            src_dir: src_dir.clone(),
-            output_dir: output_dir.clone(),
+            src_dirs: src_dirs.to_vec(),
+            files_from: String::from(""),
+            output_dir: output_dir.to_string(),
+            format: OutputFormat::Sql,
+            normalize: NormalizeType::Full,
+            placeholder: PlaceholderType::Dialect,
+            audit: AuditType::Disabled,
+            report: ReportType::Disabled,
+            config_file: String::from(""),
+            spring_boot: false,
+            includes: Vec::new(),
+            excludes: Vec::new(),
+            default_excludes: true,
+            gitignore: true,
+            archives: false,
+            follow_links: false,
+            encoding: String::from(""),
+            strict: false,
+            archive_entities: false,
             fast_fail: false,
             show_version: false,
             opts,
-        };
+        }
     }
 
     fn fail(opts: Options) -> Self {
-        return Args {
+        Args {
             mode: XBatisMode::NotSupported,
             db_type: DbType::Unknown,
-            src_dir: String::from(""),
+            src_dirs: Vec::new(),
+            files_from: String::from(""),
             output_dir: String::from(""),
+            format: OutputFormat::Unknown,
+            normalize: NormalizeType::Unknown,
+            placeholder: PlaceholderType::Unknown,
+            audit: AuditType::Unknown,
+            report: ReportType::Unknown,
+            config_file: String::from(""),
+            spring_boot: false,
+            includes: Vec::new(),
+            excludes: Vec::new(),
+            default_excludes: true,
+            gitignore: true,
+            archives: false,
+            follow_links: false,
+            encoding: String::from(""),
+            strict: false,
+            archive_entities: false,
             fast_fail: true,
             show_version: false,
             opts,
-        };
+        }
     }
 
     fn help(opts: Options) -> Self {
-        return Args {
+        Args {
             mode: XBatisMode::NotSupported,
             db_type: DbType::Unknown,
-            src_dir: String::from(""),
+            src_dirs: Vec::new(),
+            files_from: String::from(""),
             output_dir: String::from(""),
+            format: OutputFormat::Unknown,
+            normalize: NormalizeType::Unknown,
+            placeholder: PlaceholderType::Unknown,
+            audit: AuditType::Unknown,
+            report: ReportType::Unknown,
+            config_file: String::from(""),
+            spring_boot: false,
+            includes: Vec::new(),
+            excludes: Vec::new(),
+            default_excludes: true,
+            gitignore: true,
+            archives: false,
+            follow_links: false,
+            encoding: String::from(""),
+            strict: false,
+            archive_entities: false,
             fast_fail: false,
             show_version: true,
             opts,
-        };
+        }
     }
 }
 
@@ -100,8 +297,25 @@ pub fn check_args() -> Args {
     let mode_ibatis = matches.opt_present("i");
     let mode_mybatis = matches.opt_present("m");
     let o_db_type = matches.opt_str("t");
-    let src_dir = matches.opt_str("s");
+    let src_dirs = matches.opt_strs("s");
+    let files_from = matches.opt_str("files-from");
     let output_dir = matches.opt_str("o");
+    let o_format = matches.opt_str("f");
+    let o_normalize = matches.opt_str("n");
+    let o_placeholder = matches.opt_str("p");
+    let o_audit = matches.opt_str("a");
+    let o_report = matches.opt_str("r");
+    let o_config = matches.opt_str("c");
+    let spring_boot = matches.opt_present("b");
+    let includes = matches.opt_strs("include");
+    let excludes = matches.opt_strs("exclude");
+    let no_default_excludes = matches.opt_present("no-default-excludes");
+    let no_gitignore = matches.opt_present("no-gitignore");
+    let archives = matches.opt_present("z");
+    let follow_links = matches.opt_present("L");
+    let encoding = matches.opt_str("e").unwrap_or_default();
+    let strict = matches.opt_present("strict");
+    let archive_entities = matches.opt_present("archive-entities");
     if help {
         return Args::fail(opts);
     } else if version {
@@ -112,47 +326,207 @@ pub fn check_args() -> Args {
         fail!("must choose in iBATIS mode or MyBatis mode", opts);
     } else if o_db_type.is_none() {
         fail!("must define the db type", opts);
-    } else if src_dir.is_none() {
-        fail!("must define the source directory", opts);
+    } else if src_dirs.is_empty() && files_from.is_none() {
+        fail!("must define the source directory or the file list", opts);
     } else if output_dir.is_none() {
         fail!("must define the output directory", opts);
+    } else if !encoding.is_empty() && find_encoding(&encoding).is_none() {
+        fail!("unknown encoding, try GBK, GB18030, Big5 or UTF-8", opts);
     }
     let db_type = DbType::from(o_db_type.unwrap().to_ascii_lowercase().as_str());
-    match db_type {
-        DbType::Unknown => {
-            fail!("must choose db type in oracle or mysql", opts);
-        }
-        _ => {}
+    if let DbType::Unknown = db_type {
+        fail!(
+            "must choose db type in oracle, mysql, postgresql, sqlserver, db2, h2, sqlite, dm, kingbase, oceanbase-oracle, oceanbase-mysql or gaussdb",
+            opts
+        );
+    }
+    let format = match o_format {
+        Some(name) => OutputFormat::from(name.to_ascii_lowercase().as_str()),
+        None => OutputFormat::Sql,
+    };
+    if let OutputFormat::Unknown = format {
+        fail!(
+            "must choose output format in sql, sqlite, html or markdown",
+            opts
+        );
+    }
+    let normalize = match o_normalize {
+        Some(name) => NormalizeType::from(name.to_ascii_lowercase().as_str()),
+        None => NormalizeType::Full,
+    };
+    if let NormalizeType::Unknown = normalize {
+        fail!(
+            "must choose normalize mode in none, whitespace or full",
+            opts
+        );
+    }
+    let placeholder = match o_placeholder {
+        Some(name) => PlaceholderType::from(name.to_ascii_lowercase().as_str()),
+        None => PlaceholderType::Dialect,
+    };
+    if let PlaceholderType::Unknown = placeholder {
+        fail!(
+            "must choose placeholder in dialect, ?, :name, :N, $N, @pN or keep",
+            opts
+        );
+    }
+    let audit = match o_audit {
+        Some(name) => AuditType::from(name.to_ascii_lowercase().as_str()),
+        None => AuditType::Disabled,
+    };
+    if let AuditType::Unknown = audit {
+        fail!("must choose audit in injection", opts);
+    }
+    let report = match o_report {
+        Some(name) => ReportType::from(name.to_ascii_lowercase().as_str()),
+        None => ReportType::Disabled,
+    };
+    if let ReportType::Unknown = report {
+        fail!("must choose report in tables", opts);
     }
-    if mode_ibatis {
-        return Args::new(
+    let mut args = if mode_ibatis {
+        Args::new(
             XBatisMode::IBatis,
             db_type,
-            &src_dir.unwrap(),
+            &src_dirs,
             &output_dir.unwrap(),
             opts,
-        );
+        )
     } else {
-        return Args::new(
+        Args::new(
             XBatisMode::MyBatis,
             db_type,
-            &src_dir.unwrap(),
+            &src_dirs,
             &output_dir.unwrap(),
             opts,
-        );
-    }
+        )
+    };
+    args.files_from = files_from.unwrap_or_default();
+    args.format = format;
+    args.normalize = normalize;
+    args.placeholder = placeholder;
+    args.audit = audit;
+    args.report = report;
+    args.config_file = o_config.unwrap_or_default();
+    args.spring_boot = spring_boot;
+    args.includes = includes;
+    args.excludes = excludes;
+    args.default_excludes = !no_default_excludes;
+    args.gitignore = !no_gitignore;
+    args.archives = archives;
+    args.follow_links = follow_links;
+    args.encoding = encoding;
+    args.strict = strict;
+    args.archive_entities = archive_entities;
+    args
 }
 
 fn build_opts() -> Options {
     let mut opts = Options::new();
     opts.optflag("i", "ibatis", "try to parse iBATIS sqlmap files");
     opts.optflag("m", "mybatis", "try to parse MyBatis mapper files");
-    opts.optopt("t", "type", "db type", "DB");
-    opts.optopt("s", "src", "source directory", "SRC");
+    opts.optopt(
+        "t",
+        "type",
+        "db type: oracle/mysql/postgresql/sqlserver/db2/h2/sqlite/dm/kingbase/oceanbase-oracle/oceanbase-mysql/gaussdb",
+        "DB",
+    );
+    opts.optmulti("s", "src", "source directory, repeatable", "SRC");
+    opts.optopt(
+        "",
+        "files-from",
+        "read xml files to parse from PATH, one per line, - for stdin",
+        "PATH",
+    );
+    opts.optopt(
+        "c",
+        "config",
+        "mybatis-config.xml/SqlMapConfig.xml, parse the mappers it declares instead of scanning SRC",
+        "CONFIG",
+    );
+    opts.optflag(
+        "b",
+        "spring-boot",
+        "parse the mappers matched by mybatis.mapper-locations in application*.yml/properties instead of scanning SRC",
+    );
+    opts.optmulti(
+        "",
+        "include",
+        "only scan files matching GLOB under SRC, repeatable",
+        "GLOB",
+    );
+    opts.optmulti(
+        "",
+        "exclude",
+        "skip files or directories (with trailing /) matching GLOB under SRC, repeatable",
+        "GLOB",
+    );
+    opts.optflag(
+        "",
+        "no-default-excludes",
+        "also scan target/, build/, out/, node_modules/, src/test/ and VCS/IDE directories",
+    );
+    opts.optflag("", "no-gitignore", "do not respect .gitignore files");
+    opts.optflag(
+        "z",
+        "archives",
+        "also scan xml entries inside .jar/.war/.zip, including nested jars",
+    );
+    opts.optflag(
+        "L",
+        "follow-symlinks",
+        "follow symbolic links when scanning, loops are skipped",
+    );
+    opts.optopt(
+        "e",
+        "encoding",
+        "encoding of xml files, e.g. GBK/GB18030/Big5, overrides the xml declaration",
+        "ENCODING",
+    );
+    opts.optflag(
+        "",
+        "strict",
+        "exit with a non-zero code when any file fails to parse",
+    );
+    opts.optflag(
+        "",
+        "archive-entities",
+        "resolve external entities of mappers inside archives, within the same archive",
+    );
     opts.optopt("o", "output", "output directory", "OUTPUT");
+    opts.optopt(
+        "f",
+        "format",
+        "output format: sql(default)/sqlite/html/markdown",
+        "FORMAT",
+    );
+    opts.optopt(
+        "n",
+        "normalize",
+        "sql normalization: none/whitespace/full(default)",
+        "MODE",
+    );
+    opts.optopt(
+        "p",
+        "placeholder",
+        "placeholder style: dialect(default)/?/:name/:N/$N/@pN/keep",
+        "STYLE",
+    );
+    opts.optopt(
+        "a",
+        "audit",
+        "audit: injection, report ${}/$var$ substitutions and exit with 1 on high risk",
+        "AUDIT",
+    );
+    opts.optopt(
+        "r",
+        "report",
+        "report: tables, list the statements touching each table and how",
+        "REPORT",
+    );
     opts.optflag("v", "version", "show version information");
     opts.optflag("h", "help", "print this help menu");
-    return opts;
+    opts
 }
 
 /// 打印使用方法
@@ -160,7 +534,7 @@ pub fn print_usage(args: &Args) {
     print!(
         "{}",
         args.opts
-            .usage("Usage: xbatis2sql [-i|-m] -t [Oracle/MySQL] -s ... -o ...")
+            .usage("Usage: xbatis2sql [-i|-m] -t DB -s ... [-s ...] -o ... [-f ...]")
     );
 }
 
@@ -0,0 +1,240 @@
+use crate::xbatis::def::*;
+use crate::xbatis::sql_tokenizer::*;
+use log::*;
+use std::process;
+use std::*;
+
+/// 风险等级
+#[derive(Clone, Copy, PartialEq, PartialOrd)]
+pub enum Risk {
+    High,
+    Medium,
+}
+
+impl Risk {
+    pub fn name(&self) -> &str {
+        match self {
+            Risk::High => "HIGH",
+            Risk::Medium => "MEDIUM",
+        }
+    }
+}
+
+/// 文本替换所处的 `sql` 上下文
+#[derive(Clone, Copy)]
+pub enum Context {
+    /// 字符串字面量中，如 `'%${name}%'`
+    StringLiteral,
+    /// 比较运算的值，如 `id = ${id}`
+    WhereValue,
+    /// 整个条件，如 `WHERE ${condition}`
+    WhereClause,
+    /// `IN` 列表，如 `IN (${ids})`
+    InList,
+    /// `VALUES` 列表
+    Values,
+    /// `ORDER BY` / `GROUP BY`
+    OrderBy,
+    /// `LIMIT` / `OFFSET`
+    Limit,
+    /// 查询列
+    Column,
+    /// 表名
+    TableName,
+    Unknown,
+}
+
+impl Context {
+    pub fn name(&self) -> &str {
+        match self {
+            Context::StringLiteral => "string literal",
+            Context::WhereValue => "WHERE value",
+            Context::WhereClause => "WHERE clause",
+            Context::InList => "IN list",
+            Context::Values => "VALUES",
+            Context::OrderBy => "ORDER BY",
+            Context::Limit => "LIMIT",
+            Context::Column => "column",
+            Context::TableName => "table name",
+            Context::Unknown => "unknown",
+        }
+    }
+
+    pub fn risk(&self) -> Risk {
+        match self {
+            Context::StringLiteral
+            | Context::WhereValue
+            | Context::WhereClause
+            | Context::InList
+            | Context::Values
+            // 排序、查询列及表名无法使用绑定参数，是最常见的注入点
+            | Context::OrderBy
+            | Context::Column
+            | Context::TableName => Risk::High,
+            Context::Limit | Context::Unknown => Risk::Medium,
+        }
+    }
+}
+
+/// 审计发现
+pub struct Finding {
+    pub filename: String,
+    pub line: u32,
+    pub id: String,
+    /// 文本替换，如 `${orderBy}`
+    pub text: String,
+    pub context: Context,
+    /// 所在位置附近的语句
+    pub snippet: String,
+}
+
+/// 审计 `${...}` / `$...$` 文本替换，写入 `injection-audit.txt`，有高风险时返回 `true`
+pub fn audit_injection(output_dir: &String, parsed_files: &Vec<XmlParsedFile>) -> bool {
+    let mut findings: Vec<Finding> = Vec::new();
+    for parsed_file in parsed_files {
+        for result in &parsed_file.results {
+            collect_findings(&mut findings, parsed_file, result);
+        }
+    }
+    findings.sort_by(|a, b| {
+        a.context
+            .risk()
+            .partial_cmp(&b.context.risk())
+            .unwrap()
+            .then(a.filename.cmp(&b.filename))
+            .then(a.line.cmp(&b.line))
+    });
+    let high = findings
+        .iter()
+        .filter(|f| f.context.risk() == Risk::High)
+        .count();
+    let report_file = output_dir.to_string() + "/injection-audit.txt";
+    info!(
+        "write to {:?}, findings: {:?}, high risk: {:?}",
+        report_file,
+        findings.len(),
+        high
+    );
+    let mut report = String::new();
+    for finding in &findings {
+        let line = format!(
+            "[{}] {}:{} {} {} in {}",
+            finding.context.risk().name(),
+            finding.filename,
+            finding.line,
+            finding.id,
+            finding.text,
+            finding.context.name()
+        );
+        if finding.context.risk() == Risk::High {
+            warn!("{}", line);
+        }
+        report += &line;
+        report += "\n    ";
+        report += &finding.snippet;
+        report += "\n";
+    }
+    if fs::write(&report_file, report).is_err() {
+        warn!("try to write {:?} failed", report_file);
+        process::exit(-1);
+    }
+    high > 0
+}
+
+/// 逐个词法单元确定文本替换所处的上下文
+fn collect_findings(findings: &mut Vec<Finding>, parsed_file: &XmlParsedFile, result: &SqlResult) {
+    if result.substitutions.is_empty() {
+        return;
+    }
+    let mut clause = String::from("");
+    let mut last = String::from("");
+    let mut occurrences: Vec<String> = Vec::new();
+    let mut distinct: Vec<&String> = Vec::new();
+    for text in &result.substitutions {
+        if !distinct.contains(&text) {
+            distinct.push(text);
+        }
+    }
+    for token in tokenize(&result.raw_sql) {
+        // 一个词法单元可含多个文本替换，如 `'${a}_${b}'`，按出现顺序逐个审计
+        let mut matched: Vec<(usize, &String)> = Vec::new();
+        for text in &distinct {
+            for (i, _) in token.text.match_indices(text.as_str()) {
+                matched.push((i, text));
+            }
+        }
+        matched.sort_by_key(|(i, _)| *i);
+        for (_, text) in matched {
+            let context = if token.kind == TokenKind::Literal {
+                Context::StringLiteral
+            } else {
+                detect_context(&clause, &last)
+            };
+            let nth = occurrences.iter().filter(|o| *o == text).count();
+            findings.push(Finding {
+                filename: parsed_file.filename.clone(),
+                line: locate_line(result, text, nth),
+                id: result.id.clone(),
+                text: text.clone(),
+                context,
+                snippet: snippet(&result.raw_sql, text, nth),
+            });
+            occurrences.push(text.clone());
+        }
+        match token.kind {
+            TokenKind::Whitespace | TokenKind::Comment => {}
+            TokenKind::Word => {
+                let word = token.text.to_ascii_uppercase();
+                if is_keyword(&word) && word != "BY" {
+                    clause = word.clone();
+                }
+                last = word;
+            }
+            _ => last = token.text.clone(),
+        }
+    }
+}
+
+/// 根据最近的子句关键字及前一个词法单元判断上下文
+fn detect_context(clause: &str, last: &str) -> Context {
+    if last == "=" || last == "<" || last == ">" || last == "LIKE" {
+        return Context::WhereValue;
+    }
+    match clause {
+        "IN" => Context::InList,
+        "VALUES" => Context::Values,
+        "WHERE" | "AND" | "OR" | "ON" | "HAVING" | "NOT" => Context::WhereClause,
+        "ORDER" | "GROUP" => Context::OrderBy,
+        "LIMIT" | "OFFSET" | "FETCH" | "ROWS" => Context::Limit,
+        "SELECT" | "DISTINCT" | "SET" => Context::Column,
+        "FROM" | "JOIN" | "INTO" | "UPDATE" | "TABLE" => Context::TableName,
+        _ => Context::Unknown,
+    }
+}
+
+/// 在语句中定位第 `nth` 次出现的文本替换所在行，来自子句时退回到语句所在行
+fn locate_line(result: &SqlResult, text: &str, nth: usize) -> u32 {
+    let start = result.span.start.line;
+    match result.xml.match_indices(text).nth(nth) {
+        Some((i, _)) => start + result.xml[..i].matches('\n').count() as u32,
+        None => start,
+    }
+}
+
+/// 截取第 `nth` 次出现的文本替换附近的语句，合并空白
+fn snippet(sql: &str, text: &str, nth: usize) -> String {
+    let flat: Vec<char> = sql
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+        .chars()
+        .collect();
+    let flat_sql: String = flat.iter().collect();
+    let pos = match flat_sql.match_indices(text).nth(nth) {
+        Some((i, _)) => flat_sql[..i].chars().count(),
+        None => 0,
+    };
+    let from = pos.saturating_sub(40);
+    let to = cmp::min(flat.len(), pos + text.chars().count() + 40);
+    flat[from..to].iter().collect()
+}
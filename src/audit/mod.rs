@@ -0,0 +1,2 @@
+/// `SQL` 注入审计
+pub mod injection_auditor;
@@ -2,8 +2,12 @@
 
 /// 解析参数
 mod args;
+/// 审计
+mod audit;
 /// 日志处置
 mod logit;
+/// 报告
+mod report;
 /// 保存
 mod save;
 /// 扫描器
@@ -11,12 +15,31 @@ mod scan;
 /// 解析器
 mod xbatis;
 
+use args::args_parser::AuditType;
+use args::args_parser::NormalizeType;
+use args::args_parser::OutputFormat;
+use args::args_parser::PlaceholderType;
+use args::args_parser::ReportType;
 use args::args_parser::XBatisMode::*;
 use args::args_parser::*;
+use audit::injection_auditor::*;
 use log::*;
 use logit::log_initializer::*;
+use report::table_reporter::*;
+use save::html_saver::*;
+use save::markdown_saver::*;
 use save::sql_saver::*;
+use save::sqlite_saver::*;
+use scan::archive_scanner::*;
+use scan::config_scanner::*;
+use scan::content_dedup::*;
+use scan::path_filter::*;
+use scan::spring_boot_scanner::*;
+use scan::text_decoder::*;
 use scan::xml_scanner::*;
+use std::fs;
+use std::process;
+use xbatis::config_loader::*;
 use xbatis::def::*;
 use xbatis::ibatis_parser::*;
 use xbatis::mybatis_parser::*;
@@ -30,32 +53,153 @@ fn main() {
     } else if args.show_version {
         print_version();
     } else {
-        parse_xbatis_xml(args.mode, args.db_type, &args.src_dir, &args.output_dir);
+        parse_xbatis_xml(args);
     }
 }
 
 /// 选择并执行对应的解析器
-fn parse_xbatis_xml(mode: XBatisMode, db_type: DbType, src_dir: &String, output_dir: &String) {
+fn parse_xbatis_xml(args: Args) {
+    let src_dirs = &args.src_dirs;
+    let output_dir = &args.output_dir;
     init_logger();
     info!(
         "try to parse files in {:?}, fetch sql to {:?}",
-        src_dir, output_dir
+        src_dirs, output_dir
     );
     let mut files: Vec<String> = Vec::new();
-    scan(&mut files, src_dir);
-    let parser = choose_parser(mode, convert(db_type));
-    let sql_store = parser.parse(&files);
-    save(output_dir, sql_store);
+    let mut config_file = args.config_file.clone();
+    if args.spring_boot {
+        let config_location = scan_spring_boot(&mut files, src_dirs);
+        if config_file.is_empty() {
+            config_file = config_location;
+        }
+    }
+    let encoding = find_encoding(&args.encoding);
+    let mut config = if config_file.is_empty() {
+        let mut config = XBatisConfig::new();
+        config.encoding = encoding;
+        config
+    } else {
+        scan_config(&mut files, &config_file, src_dirs, encoding);
+        load_config(&config_file, src_dirs, encoding)
+    };
+    config.source_roots = src_dirs
+        .iter()
+        .filter_map(|src_dir| fs::canonicalize(src_dir).ok())
+        .collect();
+    config.archive_entities = args.archive_entities;
+    // 过滤规则在解析器取走参数之前构建，扫描时要用解析器识别归档中的映射文件
+    let filters: Vec<PathFilter> = if config_file.is_empty() && !args.spring_boot {
+        src_dirs
+            .iter()
+            .map(|src_dir| build_filter(&args, src_dir))
+            .collect()
+    } else {
+        Vec::new()
+    };
+    let dialect_type = convert(args.db_type);
+    let placeholder_style = convert_placeholder(args.placeholder, &dialect_type);
+    let parser = choose_parser(
+        args.mode,
+        dialect_type,
+        convert_normalize(args.normalize),
+        placeholder_style,
+        config,
+    );
+    for (src_dir, filter) in src_dirs.iter().zip(&filters) {
+        scan(
+            &mut files,
+            src_dir,
+            filter,
+            args.archives,
+            args.follow_links,
+            &|file, bytes| parser.detect_bytes(file, bytes),
+        );
+    }
+    if !args.files_from.is_empty() {
+        read_file_list(&mut files, &args.files_from);
+    }
+    parser.retain_matched(&mut files);
+    let mut duplicates = dedup_files(&mut files);
+    retain_cached_entries(&files);
+    let mut parsed_files = parser.parse(&files);
+    retain_cached_entries(&[]);
+    for parsed_file in &mut parsed_files {
+        if let Some(paths) = duplicates.remove(&parsed_file.filename) {
+            parsed_file.duplicates = paths;
+        }
+    }
+    save_result(args.format, output_dir, &parsed_files);
+    let failed = parsed_files.iter().filter(|f| f.error.is_some()).count();
+    if failed > 0 {
+        warn!(
+            "{} file(s) failed to parse, results of them are incomplete",
+            failed
+        );
+    }
+    if let ReportType::Tables = args.report {
+        report_tables(output_dir, &parsed_files)
+    }
+    match args.audit {
+        AuditType::Injection if audit_injection(output_dir, &parsed_files) => {
+            process::exit(1);
+        }
+        _ => {}
+    }
+    if args.strict && failed > 0 {
+        process::exit(1);
+    }
 }
 
-fn choose_parser(mode: XBatisMode, dialect_type: DialectType) -> Box<dyn Parser> {
-    match mode {
-        IBatis => {
-            return Box::new(create_ibatis_parser(dialect_type));
-        }
-        MyBatis => {
-            return Box::new(create_mybatis_parser(dialect_type));
+/// 按 `--include` / `--exclude` 等参数构建扫描时的路径过滤
+fn build_filter(args: &Args, src_dir: &String) -> PathFilter {
+    let r = PathFilter::new(
+        src_dir,
+        &args.includes,
+        &args.excludes,
+        args.default_excludes,
+        args.gitignore,
+    );
+    match r {
+        Ok(filter) => filter,
+        Err(e) => {
+            warn!("invalid glob: {}", e);
+            process::exit(-1);
         }
+    }
+}
+
+/// 按输出格式保存
+fn save_result(format: OutputFormat, output_dir: &String, parsed_files: &Vec<XmlParsedFile>) {
+    match format {
+        OutputFormat::Sql => save(output_dir, parsed_files),
+        OutputFormat::SQLite => save_sqlite(output_dir, parsed_files),
+        OutputFormat::Html => save_html(output_dir, parsed_files),
+        OutputFormat::Markdown => save_markdown(output_dir, parsed_files),
+        _ => panic!("unknown output format"),
+    }
+}
+
+fn choose_parser(
+    mode: XBatisMode,
+    dialect_type: DialectType,
+    normalize_mode: NormalizeMode,
+    placeholder_style: PlaceholderStyle,
+    config: XBatisConfig,
+) -> Box<dyn Parser> {
+    match mode {
+        IBatis => Box::new(create_ibatis_parser(
+            dialect_type,
+            normalize_mode,
+            placeholder_style,
+            config,
+        )),
+        MyBatis => Box::new(create_mybatis_parser(
+            dialect_type,
+            normalize_mode,
+            placeholder_style,
+            config,
+        )),
         _ => {
             panic!("not supported mode");
         }
@@ -63,9 +207,44 @@ fn choose_parser(mode: XBatisMode, dialect_type: DialectType) -> Box<dyn Parser>
 }
 
 fn convert(db_type: DbType) -> DialectType {
-    return match db_type {
+    match db_type {
         DbType::Oracle => DialectType::Oracle,
         DbType::MySQL => DialectType::MySQL,
+        DbType::PostgreSQL => DialectType::PostgreSQL,
+        DbType::SQLServer => DialectType::SQLServer,
+        DbType::DB2 => DialectType::DB2,
+        DbType::H2 => DialectType::H2,
+        DbType::SQLite => DialectType::SQLite,
+        DbType::DM => DialectType::DM,
+        DbType::KingbaseES => DialectType::KingbaseES,
+        DbType::OceanBaseOracle => DialectType::OceanBaseOracle,
+        DbType::OceanBaseMySQL => DialectType::OceanBaseMySQL,
+        DbType::GaussDB => DialectType::GaussDB,
         _ => panic!("unknown dialect type"),
-    };
+    }
+}
+
+fn convert_normalize(normalize: NormalizeType) -> NormalizeMode {
+    match normalize {
+        NormalizeType::None => NormalizeMode::None,
+        NormalizeType::Whitespace => NormalizeMode::Whitespace,
+        NormalizeType::Full => NormalizeMode::Full,
+        _ => panic!("unknown normalize mode"),
+    }
+}
+
+fn convert_placeholder(
+    placeholder: PlaceholderType,
+    dialect_type: &DialectType,
+) -> PlaceholderStyle {
+    match placeholder {
+        PlaceholderType::Dialect => dialect_type.placeholder_style(),
+        PlaceholderType::Question => PlaceholderStyle::Fixed("?"),
+        PlaceholderType::Named => PlaceholderStyle::Named,
+        PlaceholderType::ColonIndex => PlaceholderStyle::Indexed(":"),
+        PlaceholderType::DollarIndex => PlaceholderStyle::Indexed("$"),
+        PlaceholderType::AtIndex => PlaceholderStyle::Indexed("@p"),
+        PlaceholderType::Keep => PlaceholderStyle::Keep,
+        _ => panic!("unknown placeholder style"),
+    }
 }
@@ -0,0 +1,2 @@
+/// 表到语句的影响报告
+pub mod table_reporter;
@@ -0,0 +1,96 @@
+use crate::xbatis::def::*;
+use log::*;
+use std::collections::BTreeMap;
+use std::process;
+use std::*;
+
+/// 引用某个表的语句
+struct Usage {
+    filename: String,
+    line: u32,
+    id: String,
+    /// 访问方式，如 `SELECT, UPDATE`
+    access: String,
+    /// 引用该表的列
+    columns: Vec<String>,
+    /// 语句中无法确定所属表的列，可能属于该表
+    unresolved_columns: Vec<String>,
+}
+
+/// 按表汇总引用它的语句，写入 `table-report.txt`；表按名称排序，语句按文件及行号排序
+pub fn report_tables(output_dir: &String, parsed_files: &Vec<XmlParsedFile>) {
+    let mut tables: BTreeMap<String, Vec<Usage>> = BTreeMap::new();
+    for parsed_file in parsed_files {
+        for result in &parsed_file.results {
+            for table_ref in &result.table_refs {
+                tables
+                    .entry(table_ref.table.clone())
+                    .or_default()
+                    .push(Usage {
+                        filename: parsed_file.filename.clone(),
+                        line: result.span.start.line,
+                        id: qualified_id(&parsed_file.namespace, &result.id),
+                        access: table_ref.access_names(),
+                        columns: table_ref.columns.clone(),
+                        unresolved_columns: result.unresolved_columns.clone(),
+                    });
+            }
+        }
+    }
+    let report_file = output_dir.to_string() + "/table-report.txt";
+    info!("write to {:?}, tables: {:?}", report_file, tables.len());
+    let mut report = String::new();
+    for (table, usages) in &mut tables {
+        usages.sort_by(|a, b| a.filename.cmp(&b.filename).then(a.line.cmp(&b.line)));
+        report += &format!("{} ({})\n", table, summarize(usages));
+        for usage in usages.iter() {
+            report += &format!(
+                "    [{}] {}:{} {}\n",
+                usage.access, usage.filename, usage.line, usage.id
+            );
+            if !usage.columns.is_empty() {
+                report += &format!("        columns: {}\n", usage.columns.join(", "));
+            }
+            if !usage.unresolved_columns.is_empty() {
+                report += &format!(
+                    "        unresolved: {}\n",
+                    usage.unresolved_columns.join(", ")
+                );
+            }
+        }
+    }
+    if fs::write(&report_file, report).is_err() {
+        warn!("try to write {:?} failed", report_file);
+        process::exit(-1);
+    }
+}
+
+/// `3 statements: SELECT 2, DELETE 1`
+fn summarize(usages: &Vec<Usage>) -> String {
+    let mut counts: Vec<(&str, usize)> = Vec::new();
+    for usage in usages {
+        for access in usage.access.split(", ") {
+            match counts.iter_mut().find(|(name, _)| *name == access) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((access, 1)),
+            }
+        }
+    }
+    format!(
+        "{} statement(s): {}",
+        usages.len(),
+        counts
+            .iter()
+            .map(|(name, count)| format!("{} {}", name, count))
+            .collect::<Vec<String>>()
+            .join(", ")
+    )
+}
+
+/// `namespace.id`，没有命名空间时为 `id`
+fn qualified_id(namespace: &String, id: &str) -> String {
+    if namespace.is_empty() {
+        return id.to_string();
+    }
+    namespace.to_string() + "." + id
+}
@@ -0,0 +1,295 @@
+use crate::xbatis::def::*;
+use lazy_static::*;
+use log::*;
+use regex::Regex;
+use std::process;
+use std::*;
+
+lazy_static! {
+    /// `xml` 中的参数
+    static ref XML_PARAM_RE: Regex =
+        Regex::new("#\\{[^#{}]+\\}|\\$\\{[^${}]+\\}|#[^#&;\\s]+#|\\$[^$&;\\s]+\\$").unwrap();
+}
+
+/// 内嵌样式，报告不依赖外部资源
+const STYLE: &str = "
+body { font-family: sans-serif; margin: 2em; color: #222; }
+table { border-collapse: collapse; }
+th, td { border: 1px solid #ccc; padding: 4px 8px; text-align: left; vertical-align: top; }
+th { background: #f0f0f0; }
+.side { display: flex; gap: 1em; }
+.side > div { flex: 1; min-width: 0; }
+pre { background: #f8f8f8; border: 1px solid #ddd; padding: 8px; white-space: pre-wrap; word-break: break-all; }
+mark { background: #ffe08a; }
+.error { color: #b00020; }
+";
+
+/// 保存为 `html/` 下的静态报告
+pub fn save_html(output_dir: &String, parsed_files: &[XmlParsedFile]) {
+    let html_dir = output_dir.to_string() + "/html";
+    info!("write to {:?}, files: {:?}", html_dir, parsed_files.len());
+    if fs::create_dir_all(&html_dir).is_err() {
+        warn!("try to create {:?} failed", html_dir);
+        process::exit(-1);
+    }
+    write_page(
+        &html_dir,
+        "index.html",
+        "xbatis2sql",
+        &render_index(parsed_files),
+    );
+    for (i, parsed_file) in parsed_files.iter().enumerate() {
+        write_page(
+            &html_dir,
+            &mapper_page(i),
+            &parsed_file.filename,
+            &render_mapper(i, parsed_file),
+        );
+        for (j, result) in parsed_file.results.iter().enumerate() {
+            write_page(
+                &html_dir,
+                &statement_page(i, j),
+                &result.id,
+                &render_statement(i, parsed_file, result),
+            );
+        }
+    }
+}
+
+fn mapper_page(i: usize) -> String {
+    format!("m{}.html", i)
+}
+
+fn statement_page(i: usize, j: usize) -> String {
+    format!("m{}-s{}.html", i, j)
+}
+
+/// 首页，列出各文件及语句数
+fn render_index(parsed_files: &[XmlParsedFile]) -> String {
+    let mut body = String::from("<h1>xbatis2sql</h1>\n<table>\n");
+    body += "<tr><th>file</th><th>namespace</th><th>statements</th><th>error</th></tr>\n";
+    for (i, parsed_file) in parsed_files.iter().enumerate() {
+        body += &format!(
+            "<tr><td><a href=\"{}\">{}</a></td><td>{}</td><td>{}</td><td class=\"error\">{}</td></tr>\n",
+            mapper_page(i),
+            escape(&parsed_file.filename),
+            escape(&parsed_file.namespace),
+            parsed_file.results.len(),
+            match &parsed_file.error {
+                Some(error) => escape(&error.describe(&parsed_file.filename)),
+                None => String::from(""),
+            }
+        );
+    }
+    body += "</table>\n";
+    body
+}
+
+/// 文件页，列出其中的语句
+fn render_mapper(i: usize, parsed_file: &XmlParsedFile) -> String {
+    let mut body = format!(
+        "<p><a href=\"index.html\">index</a></p>\n<h1>{}</h1>\n<p>namespace: {}</p>\n",
+        escape(&parsed_file.filename),
+        escape(&parsed_file.namespace)
+    );
+    for duplicate in &parsed_file.duplicates {
+        body += &format!("<p>same as: {}</p>\n", escape(duplicate));
+    }
+    if let Some(error) = &parsed_file.error {
+        body += &format!(
+            "<p class=\"error\">parse error, incomplete: {}</p>\n",
+            escape(&error.describe(&parsed_file.filename))
+        );
+    }
+    body += "<table>\n";
+    body += "<tr><th>id</th><th>kind</th><th>line</th><th>tables</th></tr>\n";
+    for (j, result) in parsed_file.results.iter().enumerate() {
+        body += &format!(
+            "<tr><td><a href=\"{}\">{}</a></td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+            statement_page(i, j),
+            escape(&result.id),
+            result.mode.name(),
+            result.span.start.line,
+            escape(
+                &result
+                    .table_refs
+                    .iter()
+                    .map(|table_ref| format!("{} ({})", table_ref.table, table_ref.access_names()))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            )
+        );
+    }
+    body += "</table>\n";
+    body
+}
+
+/// 语句页，原始 `xml` 与整理后的语句并排展示
+fn render_statement(i: usize, parsed_file: &XmlParsedFile, result: &SqlResult) -> String {
+    let mut body = format!(
+        "<p><a href=\"index.html\">index</a> / <a href=\"{}\">{}</a></p>\n<h1>{}</h1>\n",
+        mapper_page(i),
+        escape(&parsed_file.filename),
+        escape(&result.id)
+    );
+    body += &format!(
+        "<p>kind: {}</p>\n<p>location: <code>{}</code></p>\n",
+        result.mode.name(),
+        escape(&result.span.start.location(&parsed_file.filename))
+    );
+    if !result.parameter_type.is_empty() {
+        body += &format!(
+            "<p>parameterType: <code>{}</code></p>\n",
+            escape(&result.parameter_type)
+        );
+    }
+    if !result.result_type.is_empty() {
+        body += &format!(
+            "<p>resultType: <code>{}</code></p>\n",
+            escape(&result.result_type)
+        );
+    }
+    body += "<div class=\"side\">\n";
+    body += &format!(
+        "<div><h2>XML</h2><pre>{}</pre></div>\n",
+        highlight(
+            &result.xml,
+            XML_PARAM_RE.find_iter(&result.xml).map(|m| m.range())
+        )
+    );
+    body += &format!(
+        "<div><h2>SQL</h2><pre>{};</pre></div>\n",
+        highlight(&result.sql, result.marked_spans.iter().cloned())
+    );
+    body += "</div>\n";
+    body += &format!(
+        "<h2>raw</h2><pre>{}</pre>\n",
+        highlight(
+            &result.raw_sql,
+            XML_PARAM_RE.find_iter(&result.raw_sql).map(|m| m.range())
+        )
+    );
+    body += &render_list(
+        "includes",
+        &result
+            .include_sites
+            .iter()
+            .map(|site| site.refid.clone() + " @ " + &site.pos.location(&parsed_file.filename))
+            .collect(),
+    );
+    body += &render_params(&result.params);
+    body += &render_result_mappings(&result.result_mappings);
+    body += &render_list("substitutions", &result.substitutions);
+    body += &render_tables(&result.table_refs);
+    body += &render_list("unresolved columns", &result.unresolved_columns);
+    body
+}
+
+/// 按结果类型自动映射的列与属性
+fn render_result_mappings(mappings: &[ResultMapping]) -> String {
+    if mappings.is_empty() {
+        return String::from("");
+    }
+    let mut body = String::from("<h2>result mappings</h2>\n<table>\n");
+    body += "<tr><th>column</th><th>property</th></tr>\n";
+    for mapping in mappings {
+        body += &format!(
+            "<tr><td><code>{}</code></td><td><code>{}</code></td></tr>\n",
+            escape(&mapping.column),
+            escape(&mapping.property)
+        );
+    }
+    body += "</table>\n";
+    body
+}
+
+/// 涉及的表：访问方式、别名及列
+fn render_tables(table_refs: &Vec<TableRef>) -> String {
+    if table_refs.is_empty() {
+        return String::from("");
+    }
+    let mut body = String::from("<h2>tables</h2>\n<table>\n");
+    body += "<tr><th>table</th><th>access</th><th>aliases</th><th>columns</th></tr>\n";
+    for table_ref in table_refs {
+        body += &format!(
+            "<tr><td><code>{}</code></td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+            escape(&table_ref.table),
+            table_ref.access_names(),
+            escape(&table_ref.aliases.join(", ")),
+            escape(&table_ref.columns.join(", "))
+        );
+    }
+    body += "</table>\n";
+    body
+}
+
+fn render_list(title: &str, items: &Vec<String>) -> String {
+    if items.is_empty() {
+        return String::from("");
+    }
+    let mut body = format!("<h2>{}</h2>\n<ol>\n", title);
+    for item in items {
+        body += &format!("<li><code>{}</code></li>\n", escape(item));
+    }
+    body += "</ol>\n";
+    body
+}
+
+/// 参数表
+fn render_params(params: &[SqlParam]) -> String {
+    if params.is_empty() {
+        return String::from("");
+    }
+    let mut body = String::from("<h2>parameters</h2>\n<table>\n");
+    body += "<tr><th>#</th><th>property</th><th>jdbcType</th><th>javaType</th><th>mode</th><th>typeHandler</th><th>numericScale</th></tr>\n";
+    for (i, param) in params.iter().enumerate() {
+        body += &format!(
+            "<tr><td>{}</td><td><code>{}</code></td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+            i + 1,
+            escape(&param.property),
+            escape(&param.jdbc_type),
+            escape(&param.java_type),
+            escape(&param.mode),
+            escape(&param.type_handler),
+            escape(&param.numeric_scale)
+        );
+    }
+    body += "</table>\n";
+    body
+}
+
+/// 转义后用 `<mark>` 标出给定区间的文本
+fn highlight(text: &str, spans: impl Iterator<Item = ops::Range<usize>>) -> String {
+    let mut html = String::new();
+    let mut last = 0;
+    for span in spans {
+        html += &escape(&text[last..span.start]);
+        html += "<mark>";
+        html += &escape(&text[span.clone()]);
+        html += "</mark>";
+        last = span.end;
+    }
+    html += &escape(&text[last..]);
+    html
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn write_page(html_dir: &String, page: &str, title: &str, body: &String) {
+    let html = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>{}</style>\n</head>\n<body>\n{}</body>\n</html>\n",
+        escape(title),
+        STYLE,
+        body
+    );
+    let path = html_dir.to_string() + "/" + page;
+    if fs::write(&path, html).is_err() {
+        warn!("try to write {:?} failed", path);
+        process::exit(-1);
+    }
+}
@@ -0,0 +1,133 @@
+use crate::xbatis::def::*;
+use log::*;
+use std::process;
+use std::*;
+
+/// 保存为 `result.md`，便于贴入合并请求
+pub fn save_markdown(output_dir: &String, parsed_files: &Vec<XmlParsedFile>) {
+    let md_file = output_dir.to_string() + "/result.md";
+    info!("write to {:?}, files: {:?}", md_file, parsed_files.len());
+    if fs::write(&md_file, render(parsed_files)).is_err() {
+        warn!("try to write {:?} failed", md_file);
+        process::exit(-1);
+    }
+}
+
+/// 先输出语句总表，再按文件输出语句
+fn render(parsed_files: &Vec<XmlParsedFile>) -> String {
+    let mut md = String::from(
+        "| namespace | id | kind | location | tables | substitutions |\n| --- | --- | --- | --- | --- | --- |\n",
+    );
+    for parsed_file in parsed_files {
+        for result in &parsed_file.results {
+            md += &format!(
+                "| {} | {} | {} | {} | {} | {} |\n",
+                code(&parsed_file.namespace),
+                code(&result.id),
+                result.mode.name(),
+                code(&result.span.start.location(&parsed_file.filename)),
+                result
+                    .table_refs
+                    .iter()
+                    .map(|table_ref| format!(
+                        "{} ({})",
+                        code(&table_ref.table),
+                        table_ref.access_names()
+                    ))
+                    .collect::<Vec<String>>()
+                    .join(", "),
+                result
+                    .substitutions
+                    .iter()
+                    .map(|text| code(text))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            );
+        }
+    }
+    for parsed_file in parsed_files {
+        md += &format!("\n### `{}`\n", parsed_file.filename);
+        if let Some(error) = &parsed_file.error {
+            md += &format!(
+                "\n> **parse error, incomplete**: `{}`\n",
+                error.describe(&parsed_file.filename)
+            );
+        }
+        if !parsed_file.duplicates.is_empty() {
+            md += &format!(
+                "\nsame as: {}\n",
+                parsed_file
+                    .duplicates
+                    .iter()
+                    .map(|text| code(text))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            );
+        }
+        for result in &parsed_file.results {
+            md += &format!("\n**`{}`**\n\n```sql\n{};\n```\n", result.id, result.sql);
+            md += &render_tables(result);
+        }
+    }
+    md
+}
+
+/// 语句涉及的表：访问方式、别名及列
+fn render_tables(result: &SqlResult) -> String {
+    if result.table_refs.is_empty() {
+        return String::from("");
+    }
+    let mut md =
+        String::from("\n| table | access | aliases | columns |\n| --- | --- | --- | --- |\n");
+    for table_ref in &result.table_refs {
+        md += &format!(
+            "| {} | {} | {} | {} |\n",
+            code(&table_ref.table),
+            table_ref.access_names(),
+            codes(&table_ref.aliases),
+            codes(&table_ref.columns)
+        );
+    }
+    if !result.unresolved_columns.is_empty() {
+        md += &format!(
+            "\nunresolved columns: {}\n",
+            codes(&result.unresolved_columns)
+        );
+    }
+    md
+}
+
+fn codes(texts: &[String]) -> String {
+    texts
+        .iter()
+        .map(|text| code(text))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+/// 以行内代码输出，并转义表格中的竖线；
+/// 围栏比文本中最长的连续反引号多一个，文本首尾为反引号时补空格
+fn code(text: &str) -> String {
+    if text.is_empty() {
+        return String::from("");
+    }
+    let longest = text
+        .split(|c| c != '`')
+        .map(|run| run.len())
+        .max()
+        .unwrap_or(0);
+    let fence = "`".repeat(longest + 1);
+    let pad = if text.starts_with('`') || text.ends_with('`') {
+        " "
+    } else {
+        ""
+    };
+    format!(
+        "{}{}{}{}{}",
+        fence,
+        pad,
+        text.replace('|', "\\|"),
+        pad,
+        fence
+    )
+}
@@ -1,2 +1,8 @@
+/// 保存为静态 `html` 报告
+pub mod html_saver;
+/// 保存为 `markdown`
+pub mod markdown_saver;
 /// 保存
 pub mod sql_saver;
+/// 保存为 `SQLite` 数据库
+pub mod sqlite_saver;
@@ -1,3 +1,4 @@
+use crate::xbatis::def::*;
 use log::*;
 use std::fs::File;
 use std::io::Write;
@@ -7,7 +8,9 @@ use std::*;
 /// 回车
 const CRLF: [u8; 1] = [0x0a];
 
-pub fn save(output_dir: &String, sql_store: Vec<String>) {
+/// 保存为 `result.sql`
+pub fn save(output_dir: &String, parsed_files: &Vec<XmlParsedFile>) {
+    let sql_store = render(parsed_files);
     info!(
         "write to {:?}/resut.sql, size: {:?}",
         output_dir,
@@ -20,7 +23,7 @@ pub fn save(output_dir: &String, sql_store: Vec<String>) {
     }
     let mut f = r.unwrap();
     for sql in sql_store {
-        write2file(&mut f, &sql.as_bytes(), output_dir);
+        write2file(&mut f, sql.as_bytes(), output_dir);
         write2file(&mut f, &CRLF, output_dir);
     }
     write2file(&mut f, &CRLF, output_dir);
@@ -31,6 +34,31 @@ pub fn save(output_dir: &String, sql_store: Vec<String>) {
     }
 }
 
+/// 按文件、语句展开为逐行文本
+fn render(parsed_files: &Vec<XmlParsedFile>) -> Vec<String> {
+    let mut sql_store: Vec<String> = Vec::new();
+    for parsed_file in parsed_files {
+        sql_store.push("-- ".to_string() + &parsed_file.filename);
+        for duplicate in &parsed_file.duplicates {
+            sql_store.push("-- same as: ".to_string() + duplicate);
+        }
+        if let Some(error) = &parsed_file.error {
+            sql_store.push(
+                "-- PARSE ERROR, incomplete: ".to_string() + &error.describe(&parsed_file.filename),
+            );
+        }
+        for result in &parsed_file.results {
+            sql_store.push(format!(
+                "--- {} {}",
+                result.id,
+                result.span.start.location(&parsed_file.filename)
+            ));
+            sql_store.push(result.sql.clone() + ";");
+        }
+    }
+    sql_store
+}
+
 fn write2file(f: &mut File, bdata: &[u8], output_dir: &String) {
     let wr = f.write(bdata);
     if wr.is_err() {
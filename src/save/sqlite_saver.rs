@@ -0,0 +1,234 @@
+use crate::xbatis::def::*;
+use log::*;
+use rusqlite::*;
+use std::path::Path;
+use std::process;
+use std::*;
+
+/// 建表语句
+const SCHEMA: &str = "
+CREATE TABLE files (
+    id INTEGER PRIMARY KEY,
+    path TEXT NOT NULL,
+    namespace TEXT NOT NULL,
+    error TEXT,
+    error_line INTEGER,
+    error_column INTEGER
+);
+CREATE TABLE duplicates (
+    file_id INTEGER NOT NULL REFERENCES files(id),
+    path TEXT NOT NULL
+);
+CREATE TABLE statements (
+    id INTEGER PRIMARY KEY,
+    file_id INTEGER NOT NULL REFERENCES files(id),
+    statement_id TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    sql TEXT NOT NULL,
+    raw_sql TEXT NOT NULL,
+    parameter_type TEXT NOT NULL,
+    result_type TEXT NOT NULL,
+    start_line INTEGER NOT NULL,
+    start_column INTEGER NOT NULL,
+    end_line INTEGER NOT NULL,
+    end_column INTEGER NOT NULL
+);
+CREATE TABLE fragments (
+    id INTEGER PRIMARY KEY,
+    file_id INTEGER NOT NULL REFERENCES files(id),
+    fragment_id TEXT NOT NULL,
+    sql TEXT NOT NULL,
+    start_line INTEGER NOT NULL,
+    start_column INTEGER NOT NULL,
+    end_line INTEGER NOT NULL,
+    end_column INTEGER NOT NULL
+);
+CREATE TABLE includes (
+    statement_id INTEGER NOT NULL REFERENCES statements(id),
+    refid TEXT NOT NULL,
+    line INTEGER NOT NULL,
+    column INTEGER NOT NULL
+);
+CREATE TABLE parameters (
+    statement_id INTEGER NOT NULL REFERENCES statements(id),
+    position INTEGER NOT NULL,
+    expression TEXT NOT NULL,
+    property TEXT NOT NULL,
+    jdbc_type TEXT NOT NULL,
+    java_type TEXT NOT NULL,
+    mode TEXT NOT NULL,
+    type_handler TEXT NOT NULL,
+    numeric_scale TEXT NOT NULL
+);
+CREATE TABLE result_mappings (
+    statement_id INTEGER NOT NULL REFERENCES statements(id),
+    position INTEGER NOT NULL,
+    column_label TEXT NOT NULL,
+    property TEXT NOT NULL
+);
+CREATE TABLE substitutions (
+    statement_id INTEGER NOT NULL REFERENCES statements(id),
+    position INTEGER NOT NULL,
+    expression TEXT NOT NULL
+);
+CREATE TABLE table_refs (
+    statement_id INTEGER NOT NULL REFERENCES statements(id),
+    table_name TEXT NOT NULL,
+    access TEXT NOT NULL
+);
+CREATE TABLE table_aliases (
+    statement_id INTEGER NOT NULL REFERENCES statements(id),
+    table_name TEXT NOT NULL,
+    alias TEXT NOT NULL
+);
+CREATE TABLE column_refs (
+    statement_id INTEGER NOT NULL REFERENCES statements(id),
+    table_name TEXT,
+    column_name TEXT NOT NULL
+);
+";
+
+/// 保存为 `result.db`，`SQLite` 数据库
+pub fn save_sqlite(output_dir: &String, parsed_files: &Vec<XmlParsedFile>) {
+    let db_file = output_dir.to_string() + "/result.db";
+    info!("write to {:?}, files: {:?}", db_file, parsed_files.len());
+    if Path::new(&db_file).exists() && fs::remove_file(&db_file).is_err() {
+        warn!("try to remove old {:?} failed", db_file);
+        process::exit(-1);
+    }
+    let r = Connection::open(&db_file).and_then(|mut conn| write2db(&mut conn, parsed_files));
+    if let Err(e) = r {
+        warn!("try to write {:?} failed: {}", db_file, e);
+        process::exit(-1);
+    }
+}
+
+/// 在同一事务中建表并写入
+fn write2db(conn: &mut Connection, parsed_files: &Vec<XmlParsedFile>) -> Result<()> {
+    let tx = conn.transaction()?;
+    tx.execute_batch(SCHEMA)?;
+    for parsed_file in parsed_files {
+        tx.execute(
+            "INSERT INTO files (path, namespace, error, error_line, error_column) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                parsed_file.filename,
+                parsed_file.namespace,
+                parsed_file.error.as_ref().map(|e| e.message.clone()),
+                parsed_file.error.as_ref().map(|e| e.pos.line),
+                parsed_file.error.as_ref().map(|e| e.pos.column)
+            ],
+        )?;
+        let file_id = tx.last_insert_rowid();
+        for duplicate in &parsed_file.duplicates {
+            tx.execute(
+                "INSERT INTO duplicates (file_id, path) VALUES (?1, ?2)",
+                params![file_id, duplicate],
+            )?;
+        }
+        for (fragment_id, fragment) in &parsed_file.sql_part_map {
+            tx.execute(
+                "INSERT INTO fragments (file_id, fragment_id, sql, start_line, start_column, end_line, end_column) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    file_id,
+                    fragment_id,
+                    fragment.sql.trim(),
+                    fragment.span.start.line,
+                    fragment.span.start.column,
+                    fragment.span.end.line,
+                    fragment.span.end.column
+                ],
+            )?;
+        }
+        for result in &parsed_file.results {
+            write_statement(&tx, file_id, result)?;
+        }
+    }
+    tx.commit()
+}
+
+fn write_statement(tx: &Transaction, file_id: i64, result: &SqlResult) -> Result<()> {
+    tx.execute(
+        "INSERT INTO statements (file_id, statement_id, kind, sql, raw_sql, parameter_type, result_type, start_line, start_column, end_line, end_column) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+        params![
+            file_id,
+            result.id,
+            result.mode.name(),
+            result.sql,
+            result.raw_sql,
+            result.parameter_type,
+            result.result_type,
+            result.span.start.line,
+            result.span.start.column,
+            result.span.end.line,
+            result.span.end.column
+        ],
+    )?;
+    let statement_id = tx.last_insert_rowid();
+    for site in &result.include_sites {
+        tx.execute(
+            "INSERT INTO includes (statement_id, refid, line, column) VALUES (?1, ?2, ?3, ?4)",
+            params![statement_id, site.refid, site.pos.line, site.pos.column],
+        )?;
+    }
+    for (position, param) in result.params.iter().enumerate() {
+        tx.execute(
+            "INSERT INTO parameters (statement_id, position, expression, property, jdbc_type, java_type, mode, type_handler, numeric_scale) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                statement_id,
+                position as i64 + 1,
+                param.text,
+                param.property,
+                param.jdbc_type,
+                param.java_type,
+                param.mode,
+                param.type_handler,
+                param.numeric_scale
+            ],
+        )?;
+    }
+    for (position, mapping) in result.result_mappings.iter().enumerate() {
+        tx.execute(
+            "INSERT INTO result_mappings (statement_id, position, column_label, property) VALUES (?1, ?2, ?3, ?4)",
+            params![
+                statement_id,
+                position as i64 + 1,
+                mapping.column,
+                mapping.property
+            ],
+        )?;
+    }
+    for (position, expression) in result.substitutions.iter().enumerate() {
+        tx.execute(
+            "INSERT INTO substitutions (statement_id, position, expression) VALUES (?1, ?2, ?3)",
+            params![statement_id, position as i64 + 1, expression],
+        )?;
+    }
+    for table_ref in &result.table_refs {
+        for access in &table_ref.accesses {
+            tx.execute(
+                "INSERT INTO table_refs (statement_id, table_name, access) VALUES (?1, ?2, ?3)",
+                params![statement_id, table_ref.table, access.name()],
+            )?;
+        }
+        for alias in &table_ref.aliases {
+            tx.execute(
+                "INSERT INTO table_aliases (statement_id, table_name, alias) VALUES (?1, ?2, ?3)",
+                params![statement_id, table_ref.table, alias],
+            )?;
+        }
+        for column in &table_ref.columns {
+            tx.execute(
+                "INSERT INTO column_refs (statement_id, table_name, column_name) VALUES (?1, ?2, ?3)",
+                params![statement_id, table_ref.table, column],
+            )?;
+        }
+    }
+    // 无法确定所属表的列，表名为空
+    for column in &result.unresolved_columns {
+        tx.execute(
+            "INSERT INTO column_refs (statement_id, table_name, column_name) VALUES (?1, NULL, ?2)",
+            params![statement_id, column],
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,150 @@
+use super::text_decoder::*;
+use encoding_rs::Encoding;
+use lazy_static::*;
+use log::*;
+use std::collections::{HashMap, HashSet};
+use std::io::{Cursor, Read, Seek};
+use std::sync::{Arc, Mutex};
+use std::*;
+use zip::ZipArchive;
+
+lazy_static! {
+    /// 扫描时读出的待解析条目，按虚拟路径缓存，之后读取时不必再次打开归档；
+    /// 识别、去重后只留下要解析的，解析完清空
+    static ref ENTRY_CACHE: Mutex<HashMap<String, Arc<Vec<u8>>>> = Mutex::new(HashMap::new());
+}
+
+/// 虚拟路径中归档与条目的分隔符，如 `app.jar!/mapper/UserMapper.xml`
+pub const ENTRY_SEPARATOR: &str = "!/";
+
+/// 是否为 `.jar` / `.war` / `.zip` 归档
+pub fn is_archive(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    name.ends_with(".jar") || name.ends_with(".war") || name.ends_with(".zip")
+}
+
+/// 按分隔符拆分虚拟路径为归档及各层条目，分隔符之前须为归档，目录名中的 `!/` 不算；
+/// 不是虚拟路径时只有一段
+pub fn split_virtual_path(path: &str) -> Vec<&str> {
+    let mut parts: Vec<&str> = Vec::new();
+    let mut start = 0;
+    for (i, _) in path.match_indices(ENTRY_SEPARATOR) {
+        if i >= start && is_archive(&path[start..i]) {
+            parts.push(&path[start..i]);
+            start = i + ENTRY_SEPARATOR.len();
+        }
+    }
+    parts.push(&path[start..]);
+    parts
+}
+
+/// 是否为归档中条目的虚拟路径
+pub fn is_virtual_path(path: &str) -> bool {
+    split_virtual_path(path).len() > 1
+}
+
+/// 收集归档中满足 `accept` 的 `xml` 条目并缓存其内容，`accept` 接收条目的虚拟路径及内容；
+/// 嵌套的归档（如 `BOOT-INF/lib/*.jar`）一并展开
+pub fn scan_archive(
+    files: &mut Vec<String>,
+    archive_path: &String,
+    accept: &dyn Fn(&str, &[u8]) -> bool,
+) {
+    match fs::File::open(archive_path) {
+        Ok(file) => scan_entries(files, archive_path, file, accept),
+        Err(e) => warn!("try to open {:?} failed: {}", archive_path, e),
+    }
+}
+
+fn scan_entries<R: Read + Seek>(
+    files: &mut Vec<String>,
+    virtual_path: &String,
+    reader: R,
+    accept: &dyn Fn(&str, &[u8]) -> bool,
+) {
+    let mut archive = match ZipArchive::new(reader) {
+        Ok(archive) => archive,
+        Err(e) => {
+            warn!("try to open archive {:?} failed: {}", virtual_path, e);
+            return;
+        }
+    };
+    for i in 0..archive.len() {
+        let mut entry = match archive.by_index(i) {
+            Ok(entry) => entry,
+            Err(e) => {
+                warn!("try to read entry of {:?} failed: {}", virtual_path, e);
+                continue;
+            }
+        };
+        if !entry.is_file() {
+            continue;
+        }
+        let name = match entry.name() {
+            Ok(name) => name.to_string(),
+            Err(e) => {
+                warn!("try to read entry name of {:?} failed: {}", virtual_path, e);
+                continue;
+            }
+        };
+        let entry_path = virtual_path.to_string() + ENTRY_SEPARATOR + &name;
+        let is_xml = name.to_ascii_lowercase().ends_with(".xml");
+        if !is_xml && !is_archive(&name) {
+            continue;
+        }
+        let mut bytes: Vec<u8> = Vec::new();
+        if let Err(e) = entry.read_to_end(&mut bytes) {
+            warn!("try to read {:?} failed: {}", entry_path, e);
+            continue;
+        }
+        if is_xml {
+            if !accept(&entry_path, &bytes) {
+                continue;
+            }
+            debug!("file: {:?}", entry_path);
+            ENTRY_CACHE
+                .lock()
+                .unwrap()
+                .insert(entry_path.clone(), Arc::new(bytes));
+            files.push(entry_path);
+        } else {
+            scan_entries(files, &entry_path, Cursor::new(bytes), accept);
+        }
+    }
+}
+
+/// 读取文件内容，支持 `app.jar!/BOOT-INF/lib/lib.jar!/mapper/UserMapper.xml` 形式的虚拟路径；
+/// 扫描时已读出的条目直接取缓存
+pub fn read_source(path: &str) -> io::Result<Arc<Vec<u8>>> {
+    if let Some(bytes) = ENTRY_CACHE.lock().unwrap().get(path) {
+        return Ok(Arc::clone(bytes));
+    }
+    let parts = split_virtual_path(path);
+    let mut bytes = fs::read(parts[0])?;
+    for entry_name in &parts[1..] {
+        let mut archive = ZipArchive::new(Cursor::new(bytes)).map_err(io::Error::other)?;
+        let mut entry = archive.by_name(entry_name).map_err(io::Error::other)?;
+        let mut entry_bytes: Vec<u8> = Vec::new();
+        entry.read_to_end(&mut entry_bytes)?;
+        bytes = entry_bytes;
+    }
+    Ok(Arc::new(bytes))
+}
+
+/// 缓存中只留下 `files` 中的条目，如识别、去重之后
+pub fn retain_cached_entries(files: &[String]) {
+    let kept: HashSet<&str> = files.iter().map(|file| file.as_str()).collect();
+    ENTRY_CACHE
+        .lock()
+        .unwrap()
+        .retain(|path, _| kept.contains(path.as_str()));
+}
+
+/// 读取为文本，按 `encoding_override`、`BOM` 或 `xml` 声明的编码解码
+pub fn read_source_to_string(
+    path: &str,
+    encoding_override: Option<&'static Encoding>,
+) -> io::Result<String> {
+    let bytes = read_source(path)?;
+    Ok(decode(&bytes, path, encoding_override))
+}
@@ -0,0 +1,189 @@
+use super::archive_scanner::*;
+use super::text_decoder::*;
+use encoding_rs::Encoding;
+use log::*;
+use std::path::{Path, PathBuf};
+use std::*;
+use xml::reader::*;
+
+/// 类路径根目录的常见位置
+const CLASSPATH_DIRS: [&str; 2] = ["src/main/resources", "src/main/java"];
+
+/// 按 `mybatis-config.xml` / `SqlMapConfig.xml` 中声明的映射文件收集 `xml` 文件
+pub fn scan_config(
+    files: &mut Vec<String>,
+    config_file: &String,
+    src_dirs: &Vec<String>,
+    encoding: Option<&'static Encoding>,
+) {
+    let content = match read_source_to_string(config_file, encoding) {
+        Ok(content) => content,
+        Err(e) => {
+            warn!("try to read config {:?} failed: {}", config_file, e);
+            return;
+        }
+    };
+    let roots = collect_roots(config_file, src_dirs);
+    debug!("classpath roots: {:?}", roots);
+    // `<typeAliases>` 中同样有 `<package>`，只处理 `<mappers>` 中的
+    let mut in_mappers = false;
+    for e in create_xml_reader(&content) {
+        match e {
+            Ok(XmlEvent::StartElement {
+                name, attributes, ..
+            }) => {
+                let element_name = name.local_name.to_ascii_lowercase();
+                if element_name == "mappers" {
+                    in_mappers = true;
+                }
+                if element_name != "mapper"
+                    && element_name != "sqlmap"
+                    && !(element_name == "package" && in_mappers)
+                {
+                    continue;
+                }
+                for attr in &attributes {
+                    let value = attr.value.trim();
+                    match (element_name.as_str(), attr.name.local_name.as_str()) {
+                        ("mapper", "resource") | ("sqlmap", "resource") => {
+                            push_resource(files, &roots, value, config_file, true);
+                        }
+                        ("mapper", "url") | ("sqlmap", "url") => {
+                            push_url(files, value, config_file);
+                        }
+                        ("mapper", "class") => {
+                            let resource = value.replace('.', "/") + ".xml";
+                            push_resource(files, &roots, &resource, config_file, false);
+                        }
+                        ("package", "name") => {
+                            push_package(files, &roots, value, config_file);
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            Ok(XmlEvent::EndElement { name }) if name.local_name == "mappers" => {
+                in_mappers = false;
+            }
+            Err(e) => {
+                warn!("try to parse config {:?} failed: {}", config_file, e);
+                break;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// 类路径根目录：配置文件所在目录、各源目录及其下各模块的 `src/main/resources` / `src/main/java`
+pub fn collect_roots(config_file: &String, src_dirs: &Vec<String>) -> Vec<PathBuf> {
+    let mut roots: Vec<PathBuf> = Vec::new();
+    if let Some(parent) = Path::new(config_file).parent() {
+        roots.push(parent.to_path_buf());
+    }
+    for src_dir in src_dirs {
+        roots.push(PathBuf::from(src_dir));
+        for d in walkdir::WalkDir::new(src_dir).into_iter().flatten() {
+            if d.file_type().is_dir() && CLASSPATH_DIRS.iter().any(|dir| d.path().ends_with(dir)) {
+                roots.push(d.path().to_path_buf());
+            }
+        }
+    }
+    roots
+}
+
+/// `resource="org/demo/DemoMapper.xml"`，`required` 为假时（`class` 对应的 `xml`）找不到不告警
+fn push_resource(
+    files: &mut Vec<String>,
+    roots: &[PathBuf],
+    resource: &str,
+    config_file: &String,
+    required: bool,
+) {
+    if let Some(path) = resolve_resource(roots, resource) {
+        push_file(files, &path);
+    } else if required {
+        warn!(
+            "mapper {:?} referenced in {:?} not found",
+            resource, config_file
+        );
+    } else {
+        debug!("no xml for mapper class: {:?}", resource);
+    }
+}
+
+/// 在各根目录下查找类路径资源
+pub fn resolve_resource(roots: &[PathBuf], resource: &str) -> Option<PathBuf> {
+    let resource = resource
+        .trim_start_matches("classpath:")
+        .trim_start_matches('/');
+    roots
+        .iter()
+        .map(|root| root.join(resource))
+        .find(|path| path.is_file())
+}
+
+/// `file:///path/to/DemoMapper.xml` 转为本地路径，其他协议返回 `None`
+pub fn url_to_path(url: &str) -> Option<PathBuf> {
+    let rest = url.strip_prefix("file:")?.trim_start_matches("//");
+    // `file:///C:/...` 在 Windows 下去掉多余的 `/`
+    if rest.len() > 2 && rest.as_bytes()[2] == b':' {
+        return Some(PathBuf::from(&rest[1..]));
+    }
+    Some(PathBuf::from(rest))
+}
+
+/// `url="file:///path/to/DemoMapper.xml"`，仅支持本地文件
+fn push_url(files: &mut Vec<String>, url: &str, config_file: &String) {
+    let path = match url_to_path(url) {
+        Some(path) => path,
+        None => {
+            warn!(
+                "mapper url {:?} referenced in {:?} is not supported",
+                url, config_file
+            );
+            return;
+        }
+    };
+    if path.is_file() {
+        push_file(files, &path);
+    } else {
+        warn!("mapper {:?} referenced in {:?} not found", url, config_file);
+    }
+}
+
+/// `package name="org.demo.mapper"`，收集各根目录下该包中的 `xml` 文件
+fn push_package(
+    files: &mut Vec<String>,
+    roots: &Vec<PathBuf>,
+    package: &str,
+    config_file: &String,
+) {
+    let dir = package.replace('.', "/");
+    let mut found = false;
+    for root in roots {
+        let path = root.join(&dir);
+        if let Ok(entries) = fs::read_dir(&path) {
+            for entry in entries.flatten() {
+                let file = entry.path();
+                if file.is_file() && file.extension().is_some_and(|ext| ext.eq("xml")) {
+                    push_file(files, &file);
+                    found = true;
+                }
+            }
+        }
+    }
+    if !found {
+        warn!(
+            "no mapper found for package {:?} referenced in {:?}",
+            package, config_file
+        );
+    }
+}
+
+fn push_file(files: &mut Vec<String>, path: &Path) {
+    let file = path.to_string_lossy().to_string();
+    if !files.contains(&file) {
+        debug!("file: {:?}", file);
+        files.push(file);
+    }
+}
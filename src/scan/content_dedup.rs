@@ -0,0 +1,86 @@
+use super::archive_scanner::*;
+use log::*;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::*;
+
+/// 按内容去重，同一映射文件经不同路径（符号链接、多个源目录等）收集到时只保留一个，优先保留不经过符号链接的路径，
+/// 返回保留的文件及其相同内容的其他路径。
+/// 大小唯一的文件不读取；其余只记录哈希，哈希相同时再读取比较内容
+pub fn dedup_files(files: &mut Vec<String>) -> HashMap<String, Vec<String>> {
+    let sizes: Vec<Option<u64>> = files.iter().map(file_size).collect();
+    let mut size_counts: HashMap<u64, usize> = HashMap::new();
+    for size in sizes.iter().flatten() {
+        *size_counts.entry(*size).or_default() += 1;
+    }
+    let mut duplicates: HashMap<String, Vec<String>> = HashMap::new();
+    // (大小, 哈希) 到保留的文件的下标
+    let mut by_hash: HashMap<(u64, u64), Vec<usize>> = HashMap::new();
+    let mut kept: Vec<String> = Vec::new();
+    for (file, size) in files.iter().zip(sizes) {
+        let size = match size {
+            Some(size) if size_counts[&size] > 1 => size,
+            _ => {
+                kept.push(file.clone());
+                continue;
+            }
+        };
+        let content = match read_source(file) {
+            Ok(content) => content,
+            Err(_) => {
+                kept.push(file.clone());
+                continue;
+            }
+        };
+        let mut hasher = DefaultHasher::new();
+        content.hash(&mut hasher);
+        let candidates = by_hash.entry((size, hasher.finish())).or_default();
+        // 哈希相同时再读取比较内容，避免碰撞
+        let i = match candidates
+            .iter()
+            .find(|i| read_source(&kept[**i]).is_ok_and(|c| *c == *content))
+        {
+            Some(i) => *i,
+            None => {
+                candidates.push(kept.len());
+                kept.push(file.clone());
+                continue;
+            }
+        };
+        let mut duplicate = file.clone();
+        if is_real_path(file) && !is_real_path(&kept[i]) {
+            mem::swap(&mut kept[i], &mut duplicate);
+            if let Some(paths) = duplicates.remove(&duplicate) {
+                duplicates.insert(kept[i].clone(), paths);
+            }
+        }
+        info!(
+            "{:?} has the same content as {:?}, skip",
+            duplicate, kept[i]
+        );
+        duplicates
+            .entry(kept[i].clone())
+            .or_default()
+            .push(duplicate);
+    }
+    *files = kept;
+    duplicates
+}
+
+/// 文件大小，归档中的条目取已缓存的内容
+fn file_size(file: &String) -> Option<u64> {
+    if is_virtual_path(file) {
+        return read_source(file).ok().map(|content| content.len() as u64);
+    }
+    fs::metadata(file).ok().map(|metadata| metadata.len())
+}
+
+/// 路径中是否不含符号链接
+fn is_real_path(file: &String) -> bool {
+    let path = path::Path::new(file);
+    match (fs::canonicalize(path), path::absolute(path)) {
+        (Ok(canonical), Ok(absolute)) => canonical == absolute,
+        _ => true,
+    }
+}
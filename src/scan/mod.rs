@@ -1,2 +1,14 @@
+/// 扫描归档中的映射文件
+pub mod archive_scanner;
+/// 按配置文件收集映射文件
+pub mod config_scanner;
+/// 按内容去重
+pub mod content_dedup;
+/// 扫描时的路径过滤
+pub mod path_filter;
+/// 按 `Spring Boot` 配置收集映射文件
+pub mod spring_boot_scanner;
+/// 文本解码
+pub mod text_decoder;
 /// 扫描器
 pub mod xml_scanner;
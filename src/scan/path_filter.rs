@@ -0,0 +1,243 @@
+use globset::{Glob, GlobBuilder, GlobMatcher, GlobSet, GlobSetBuilder};
+use log::*;
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+use std::*;
+
+/// 默认排除的目录：依赖及版本管理目录，以及测试资源
+pub const DEFAULT_EXCLUDES: [&str; 8] = [
+    "node_modules/",
+    ".git/",
+    ".svn/",
+    ".hg/",
+    ".idea/",
+    ".gradle/",
+    ".mvn/",
+    "src/test/",
+];
+
+/// 默认排除的构建输出目录，只在模块根目录下排除，避免误伤同名的包
+const BUILD_OUTPUT_DIRS: [&str; 3] = ["target", "build", "out"];
+
+/// 模块根目录中的构建文件
+const BUILD_FILES: [&str; 4] = [
+    "pom.xml",
+    "build.gradle",
+    "build.gradle.kts",
+    "settings.gradle",
+];
+
+/// `.gitignore` 中的一条规则
+struct IgnoreRule {
+    /// `.gitignore` 所在目录
+    base: PathBuf,
+    matcher: GlobMatcher,
+    /// `!pattern`
+    negate: bool,
+    /// `pattern/`，只匹配目录
+    dir_only: bool,
+}
+
+/// 扫描时的路径过滤，模式均相对于源目录
+pub struct PathFilter {
+    root: PathBuf,
+    /// 为空时包含全部文件
+    includes: Option<GlobSet>,
+    excludes: GlobSet,
+    /// 以 `/` 结尾的排除模式，只匹配目录
+    exclude_dirs: GlobSet,
+    /// 是否排除模块根目录下的构建输出目录
+    exclude_build_outputs: bool,
+    use_gitignore: bool,
+    ignore_rules: RefCell<Vec<IgnoreRule>>,
+}
+
+impl PathFilter {
+    pub fn new(
+        root: &String,
+        includes: &Vec<String>,
+        excludes: &[String],
+        use_default_excludes: bool,
+        use_gitignore: bool,
+    ) -> Result<Self, globset::Error> {
+        let mut all_excludes: Vec<String> = Vec::new();
+        if use_default_excludes {
+            all_excludes.extend(DEFAULT_EXCLUDES.iter().map(|p| p.to_string()));
+        }
+        all_excludes.extend(excludes.iter().cloned());
+        let mut exclude_builder = GlobSetBuilder::new();
+        let mut exclude_dir_builder = GlobSetBuilder::new();
+        for pattern in &all_excludes {
+            match pattern.strip_suffix('/') {
+                Some(dir) => exclude_dir_builder.add(build_glob(dir)?),
+                None => exclude_builder.add(build_glob(pattern)?),
+            };
+        }
+        let includes = if includes.is_empty() {
+            None
+        } else {
+            let mut include_builder = GlobSetBuilder::new();
+            for pattern in includes {
+                include_builder.add(build_glob(pattern)?);
+            }
+            Some(include_builder.build()?)
+        };
+        let filter = PathFilter {
+            root: PathBuf::from(root),
+            includes,
+            excludes: exclude_builder.build()?,
+            exclude_dirs: exclude_dir_builder.build()?,
+            exclude_build_outputs: use_default_excludes,
+            use_gitignore,
+            ignore_rules: RefCell::new(Vec::new()),
+        };
+        if use_gitignore {
+            filter.load_parent_gitignores();
+        }
+        Ok(filter)
+    }
+
+    /// 是否跳过，目录被跳过时不再深入
+    pub fn is_excluded(&self, path: &Path, is_dir: bool) -> bool {
+        let relative = self.relative(path);
+        if self.excludes.is_match(&relative)
+            || (is_dir && self.exclude_dirs.is_match(&relative))
+            || (is_dir && self.exclude_build_outputs && self.is_build_output(path, &relative))
+        {
+            debug!("exclude: {:?}", path);
+            return true;
+        }
+        if self.is_gitignored(path, is_dir) {
+            debug!("ignored by .gitignore: {:?}", path);
+            return true;
+        }
+        false
+    }
+
+    /// `target/` / `build/` / `out/` 位于构建文件旁时为构建输出，`src/main` 之下的同名目录为包或资源目录
+    fn is_build_output(&self, path: &Path, relative: &str) -> bool {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        if !BUILD_OUTPUT_DIRS.contains(&name.as_ref()) {
+            return false;
+        }
+        let parts: Vec<&str> = relative.split('/').collect();
+        if parts.windows(2).any(|pair| pair == ["src", "main"]) {
+            return false;
+        }
+        match path.parent() {
+            Some(parent) => BUILD_FILES.iter().any(|file| parent.join(file).exists()),
+            None => false,
+        }
+    }
+
+    /// 文件是否匹配 `--include`，未指定时总为真
+    pub fn is_included(&self, path: &Path) -> bool {
+        match &self.includes {
+            Some(includes) => includes.is_match(self.relative(path)),
+            None => true,
+        }
+    }
+
+    /// 进入目录时读取其中的 `.gitignore`
+    pub fn enter_dir(&self, dir: &Path) {
+        if self.use_gitignore {
+            self.load_gitignore(dir);
+        }
+    }
+
+    fn relative(&self, path: &Path) -> String {
+        let relative = path.strip_prefix(&self.root).unwrap_or(path);
+        relative.to_string_lossy().replace('\\', "/")
+    }
+
+    /// 源目录位于仓库子目录时，读取其上直到仓库根目录的 `.gitignore`
+    fn load_parent_gitignores(&self) {
+        let root = match fs::canonicalize(&self.root) {
+            Ok(root) => root,
+            Err(_) => return,
+        };
+        if root.join(".git").exists() {
+            return;
+        }
+        let mut dirs: Vec<PathBuf> = Vec::new();
+        for dir in root.ancestors().skip(1) {
+            dirs.push(dir.to_path_buf());
+            if dir.join(".git").exists() {
+                // 规则相对于规范化后的路径，匹配时同样规范化
+                for dir in dirs.iter().rev() {
+                    self.load_gitignore(dir);
+                }
+                return;
+            }
+        }
+    }
+
+    fn load_gitignore(&self, dir: &Path) {
+        let content = match fs::read_to_string(dir.join(".gitignore")) {
+            Ok(content) => content,
+            Err(_) => return,
+        };
+        let base = fs::canonicalize(dir).unwrap_or(dir.to_path_buf());
+        let mut rules = self.ignore_rules.borrow_mut();
+        for line in content.lines() {
+            let line = line.trim_end();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (negate, pattern) = match line.strip_prefix('!') {
+                Some(pattern) => (true, pattern),
+                None => (false, line),
+            };
+            let (dir_only, pattern) = match pattern.strip_suffix('/') {
+                Some(pattern) => (true, pattern),
+                None => (false, pattern),
+            };
+            // 含 `/` 的模式相对于 `.gitignore` 所在目录，否则匹配任意层级
+            let pattern = match pattern.strip_prefix('/') {
+                Some(pattern) => pattern.to_string(),
+                None if pattern.contains('/') => pattern.to_string(),
+                None => String::from("**/") + pattern,
+            };
+            match GlobBuilder::new(&pattern).literal_separator(true).build() {
+                Ok(glob) => rules.push(IgnoreRule {
+                    base: base.clone(),
+                    matcher: glob.compile_matcher(),
+                    negate,
+                    dir_only,
+                }),
+                Err(e) => warn!("invalid pattern {:?} in {:?}: {}", line, dir, e),
+            }
+        }
+    }
+
+    /// 与 `git` 一致，最后匹配的规则生效
+    fn is_gitignored(&self, path: &Path, is_dir: bool) -> bool {
+        let rules = self.ignore_rules.borrow();
+        if rules.is_empty() {
+            return false;
+        }
+        let path = fs::canonicalize(path).unwrap_or(path.to_path_buf());
+        let mut ignored = false;
+        for rule in rules.iter() {
+            if rule.dir_only && !is_dir {
+                continue;
+            }
+            if let Ok(relative) = path.strip_prefix(&rule.base) {
+                if rule.matcher.is_match(relative) {
+                    ignored = !rule.negate;
+                }
+            }
+        }
+        ignored
+    }
+}
+
+/// 模式匹配任意层级，如 `src/test` 匹配各模块的 `src/test`，以 `/` 开头时只匹配源目录下
+fn build_glob(pattern: &str) -> Result<Glob, globset::Error> {
+    let pattern = match pattern.strip_prefix('/') {
+        Some(pattern) => pattern.to_string(),
+        None if pattern.starts_with("**/") => pattern.to_string(),
+        None => String::from("**/") + pattern,
+    };
+    GlobBuilder::new(&pattern).literal_separator(true).build()
+}
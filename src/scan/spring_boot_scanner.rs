@@ -0,0 +1,205 @@
+use super::config_scanner::*;
+use crate::xbatis::config_loader::*;
+use globset::GlobBuilder;
+use lazy_static::*;
+use log::*;
+use regex::Regex;
+use std::path::{Path, PathBuf};
+use std::*;
+use yaml_rust2::{Yaml, YamlLoader};
+
+lazy_static! {
+    /// `application.yml` / `application-{profile}.properties` 等
+    static ref APPLICATION_RE: Regex =
+        Regex::new("^application(-[^.]+)?\\.(yml|yaml|properties)$").unwrap();
+    /// 列表下标，如 `mapper-locations[0]`
+    static ref INDEX_RE: Regex = Regex::new("\\[[0-9]+\\]").unwrap();
+}
+
+/// 资源目录
+const RESOURCES_DIR: &str = "src/main/resources";
+/// 映射文件位置的键，按 `Spring Boot` 宽松绑定规则归一化
+const MAPPER_LOCATIONS_KEYS: [&str; 2] = ["mybatis.mapperlocations", "mybatisplus.mapperlocations"];
+/// 配置文件位置的键，按 `Spring Boot` 宽松绑定规则归一化
+const CONFIG_LOCATION_KEYS: [&str; 2] = ["mybatis.configlocation", "mybatisplus.configlocation"];
+
+/// 按各模块 `application*.yml` / `application*.properties` 中的 `mybatis.mapper-locations` 收集 `xml` 文件，
+/// 返回找到的 `mybatis.config-location` 对应的文件，没有时为空串
+pub fn scan_spring_boot(files: &mut Vec<String>, src_dirs: &Vec<String>) -> String {
+    let mut resources: Vec<PathBuf> = Vec::new();
+    let mut application_files: Vec<PathBuf> = Vec::new();
+    for d in src_dirs
+        .iter()
+        .flat_map(|src_dir| walkdir::WalkDir::new(src_dir).into_iter().flatten())
+    {
+        let path = d.path();
+        if d.file_type().is_dir() && path.ends_with(RESOURCES_DIR) {
+            resources.push(path.to_path_buf());
+        } else if d.file_type().is_file()
+            && APPLICATION_RE.is_match(&d.file_name().to_string_lossy())
+            && path.parent().is_some_and(|parent| {
+                parent.ends_with(RESOURCES_DIR) || parent.ends_with("src/main/resources/config")
+            })
+        {
+            application_files.push(path.to_path_buf());
+        }
+    }
+    let mut config_file = String::from("");
+    for application_file in &application_files {
+        info!(
+            "try to read spring boot config [{}]",
+            application_file.display()
+        );
+        for (key, value) in read_application_file(application_file) {
+            let key = normalize_key(&key);
+            if MAPPER_LOCATIONS_KEYS.contains(&key.as_str()) {
+                for location in value.split(',').map(str::trim).filter(|l| !l.is_empty()) {
+                    expand_location(files, &resources, location, application_file);
+                }
+            } else if CONFIG_LOCATION_KEYS.contains(&key.as_str()) && config_file.is_empty() {
+                config_file = resolve_location(&resources, value.trim());
+            }
+        }
+    }
+    if application_files.is_empty() {
+        warn!("no spring boot config found in {:?}", src_dirs);
+    }
+    config_file
+}
+
+/// 读取为扁平的键值对，`yml` 中的层级以 `.` 连接，列表以 `[n]` 标注
+fn read_application_file(path: &Path) -> Vec<(String, String)> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) => {
+            warn!("try to read {:?} failed: {}", path, e);
+            return Vec::new();
+        }
+    };
+    if path.extension().is_some_and(|ext| ext.eq("properties")) {
+        return parse_properties(&content).into_iter().collect();
+    }
+    let mut pairs: Vec<(String, String)> = Vec::new();
+    match YamlLoader::load_from_str(&content) {
+        Ok(docs) => {
+            // 多文档（`---` 分隔的各 `profile`）全部收集
+            for doc in &docs {
+                flatten_yaml(&mut pairs, String::from(""), doc);
+            }
+        }
+        Err(e) => warn!("try to parse {:?} failed: {}", path, e),
+    }
+    pairs
+}
+
+fn flatten_yaml(pairs: &mut Vec<(String, String)>, prefix: String, node: &Yaml) {
+    match node {
+        Yaml::Hash(hash) => {
+            for (key, value) in hash {
+                let key = match key {
+                    Yaml::String(key) => key.clone(),
+                    Yaml::Integer(key) => key.to_string(),
+                    Yaml::Boolean(key) => key.to_string(),
+                    _ => continue,
+                };
+                let path = if prefix.is_empty() {
+                    key
+                } else {
+                    prefix.clone() + "." + &key
+                };
+                flatten_yaml(pairs, path, value);
+            }
+        }
+        Yaml::Array(array) => {
+            for (i, value) in array.iter().enumerate() {
+                flatten_yaml(pairs, format!("{}[{}]", prefix, i), value);
+            }
+        }
+        Yaml::String(value) | Yaml::Real(value) => pairs.push((prefix, value.clone())),
+        Yaml::Integer(value) => pairs.push((prefix, value.to_string())),
+        Yaml::Boolean(value) => pairs.push((prefix, value.to_string())),
+        _ => {}
+    }
+}
+
+/// 宽松绑定：忽略大小写、`-`、`_` 及列表下标
+fn normalize_key(key: &str) -> String {
+    INDEX_RE
+        .replace_all(key, "")
+        .to_ascii_lowercase()
+        .replace(['-', '_'], "")
+}
+
+/// 拆分为不含通配符的前缀目录及其后的通配模式
+fn split_pattern(pattern: &str) -> (String, String) {
+    let first_glob = pattern.find(['*', '?', '{', '[']).unwrap_or(pattern.len());
+    match pattern[..first_glob].rfind('/') {
+        Some(i) => (pattern[..i].to_string(), pattern[i + 1..].to_string()),
+        None => (String::from(""), pattern.to_string()),
+    }
+}
+
+/// 展开 `classpath*:mapper/**/*.xml` / `file:/path/*.xml` 等位置
+fn expand_location(
+    files: &mut Vec<String>,
+    resources: &[PathBuf],
+    location: &str,
+    application_file: &Path,
+) {
+    let (bases, pattern) = if let Some(path) = url_to_path(location) {
+        let (base, pattern) = split_pattern(&path.to_string_lossy());
+        (vec![PathBuf::from(base)], pattern)
+    } else {
+        let resource = location
+            .trim_start_matches("classpath*:")
+            .trim_start_matches("classpath:")
+            .trim_start_matches('/');
+        let (base, pattern) = split_pattern(resource);
+        let bases = resources.iter().map(|root| root.join(&base)).collect();
+        (bases, pattern)
+    };
+    let matcher = match GlobBuilder::new(&pattern).literal_separator(true).build() {
+        Ok(glob) => glob.compile_matcher(),
+        Err(e) => {
+            warn!(
+                "invalid mapper location {:?} in {:?}: {}",
+                location, application_file, e
+            );
+            return;
+        }
+    };
+    let mut found = false;
+    for base in bases.iter().filter(|base| base.is_dir()) {
+        for d in walkdir::WalkDir::new(base).into_iter().flatten() {
+            if d.file_type().is_file() && matcher.is_match(d.path().strip_prefix(base).unwrap()) {
+                let file = d.path().to_string_lossy().to_string();
+                if !files.contains(&file) {
+                    debug!("file: {:?}", file);
+                    files.push(file);
+                }
+                found = true;
+            }
+        }
+    }
+    if !found {
+        warn!(
+            "no mapper found for location {:?} in {:?}",
+            location, application_file
+        );
+    }
+}
+
+/// `classpath:mybatis-config.xml` / `file:/path/mybatis-config.xml` 转为本地文件
+fn resolve_location(resources: &[PathBuf], location: &str) -> String {
+    let path = match url_to_path(location) {
+        Some(path) => Some(path).filter(|path| path.is_file()),
+        None => resolve_resource(resources, location.trim_start_matches("classpath*:")),
+    };
+    match path {
+        Some(path) => path.to_string_lossy().to_string(),
+        None => {
+            warn!("mybatis config {:?} not found", location);
+            String::from("")
+        }
+    }
+}
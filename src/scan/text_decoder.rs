@@ -0,0 +1,65 @@
+use encoding_rs::*;
+use lazy_static::*;
+use log::*;
+use regex::bytes::Regex;
+use xml::reader::*;
+
+lazy_static! {
+    /// `xml` 声明中的编码，如 `<?xml version="1.0" encoding="GBK"?>`
+    static ref DECLARATION_RE: Regex =
+        Regex::new("^\\s*<\\?xml[^>]*?encoding\\s*=\\s*[\"']([A-Za-z0-9._:-]+)[\"']").unwrap();
+}
+
+/// 按编码标签查找，如 `GBK` / `GB18030` / `Big5`，不区分大小写
+pub fn find_encoding(label: &str) -> Option<&'static Encoding> {
+    Encoding::for_label(label.trim().as_bytes())
+}
+
+/// 解码为文本：优先按 `BOM`，其次 `encoding_override`，再次 `xml` 声明中的编码，均无时按 `UTF-8`；
+/// 无法解码的字节替换为 `U+FFFD` 并告警
+pub fn decode(bytes: &[u8], path: &str, encoding_override: Option<&'static Encoding>) -> String {
+    let bom = Encoding::for_bom(bytes);
+    let encoding = match (bom, encoding_override) {
+        (Some((encoding, _)), _) => encoding,
+        (None, Some(encoding)) => encoding,
+        (None, None) => declared_encoding(bytes, path).unwrap_or(UTF_8),
+    };
+    let bytes = match bom {
+        Some((_, bom_len)) => &bytes[bom_len..],
+        None => bytes,
+    };
+    if encoding != UTF_8 {
+        debug!("decode {:?} as {}", path, encoding.name());
+    }
+    let (text, had_errors) = encoding.decode_without_bom_handling(bytes);
+    if had_errors {
+        warn!(
+            "{:?} contains bytes invalid in {}, replaced with U+FFFD, try --encoding",
+            path,
+            encoding.name()
+        );
+    }
+    text.into_owned()
+}
+
+fn declared_encoding(bytes: &[u8], path: &str) -> Option<&'static Encoding> {
+    let head = &bytes[..bytes.len().min(256)];
+    let label = DECLARATION_RE.captures(head)?.get(1)?.as_bytes();
+    let encoding = Encoding::for_label(label);
+    if encoding.is_none() {
+        warn!(
+            "unknown encoding {:?} declared in {:?}, read as UTF-8",
+            String::from_utf8_lossy(label),
+            path
+        );
+    }
+    encoding
+}
+
+/// 读取已解码的文本，忽略声明中的编码
+pub fn create_xml_reader(content: &str) -> EventReader<&[u8]> {
+    ParserConfig::new()
+        .override_encoding(Some(xml::Encoding::Utf8))
+        .ignore_invalid_encoding_declarations(true)
+        .create_reader(content.as_bytes())
+}
@@ -1,18 +1,68 @@
+use super::archive_scanner::*;
+use super::path_filter::*;
 use log::*;
+use std::collections::HashSet;
 use std::*;
 
-/// 扫描给定的文件夹，过滤出 `xml` 文件
-pub fn scan(files: &mut Vec<String>, dir: &String) {
-    for d in walkdir::WalkDir::new(dir).into_iter() {
-        if d.is_ok() {
-            check_if_xml_file(files, d);
+/// 扫描给定的文件夹，过滤出 `xml` 文件，被排除的目录不再深入；`with_archives` 为真时展开 `.jar` / `.war` / `.zip`，
+/// 其中只收集 `detect` 识别出的映射文件；`follow_links` 为真时跟随符号链接，成环的链接跳过
+pub fn scan(
+    files: &mut Vec<String>,
+    dir: &String,
+    filter: &PathFilter,
+    with_archives: bool,
+    follow_links: bool,
+    detect: &dyn Fn(&str, &[u8]) -> bool,
+) {
+    let walker = walkdir::WalkDir::new(dir)
+        .follow_links(follow_links)
+        .into_iter()
+        .filter_entry(|e| {
+            let is_dir = e.file_type().is_dir();
+            if e.depth() > 0 && filter.is_excluded(e.path(), is_dir) {
+                return false;
+            }
+            if is_dir {
+                filter.enter_dir(e.path());
+            }
+            let archive = with_archives && is_archive(&e.file_name().to_string_lossy());
+            is_dir || archive || filter.is_included(e.path())
+        });
+    for d in walker {
+        match d {
+            Ok(entry) => {
+                if with_archives {
+                    check_if_archive(files, &entry, filter, detect);
+                }
+                check_if_xml_file(files, &entry);
+            }
+            Err(e) if e.loop_ancestor().is_some() => warn!("skip symbolic link loop: {}", e),
+            Err(e) => debug!("skip: {}", e),
         }
     }
 }
 
+/// 判断是否是归档文件。是，将其中匹配 `--include` 且为映射文件的 `xml` 条目攒入 `files`。
+fn check_if_archive(
+    files: &mut Vec<String>,
+    entry: &walkdir::DirEntry,
+    filter: &PathFilter,
+    detect: &dyn Fn(&str, &[u8]) -> bool,
+) {
+    if entry.file_type().is_file() && is_archive(&entry.file_name().to_string_lossy()) {
+        scan_archive(
+            files,
+            &entry.path().to_string_lossy().to_string(),
+            &|entry_path, bytes| {
+                let name = split_virtual_path(entry_path).pop().unwrap_or_default();
+                filter.is_included(path::Path::new(name)) && detect(entry_path, bytes)
+            },
+        );
+    }
+}
+
 /// 判断是否是 `xml` 文件。是，攒入 `files`。
-fn check_if_xml_file(files: &mut Vec<String>, d: Result<walkdir::DirEntry, walkdir::Error>) {
-    let entry = d.unwrap();
+fn check_if_xml_file(files: &mut Vec<String>, entry: &walkdir::DirEntry) {
     let file_type = entry.file_type();
     if file_type.is_file() {
         let ext = path::Path::new(entry.file_name()).extension();
@@ -22,3 +72,37 @@ fn check_if_xml_file(files: &mut Vec<String>, d: Result<walkdir::DirEntry, walkd
         }
     }
 }
+
+/// 读取文件列表，每行一个文件，`-` 表示标准输入，如 `git diff --name-only` 的输出
+pub fn read_file_list(files: &mut Vec<String>, source: &String) {
+    let r = if source == "-" {
+        io::read_to_string(io::stdin())
+    } else {
+        fs::read_to_string(source)
+    };
+    let content = match r {
+        Ok(content) => content,
+        Err(e) => {
+            warn!("try to read file list {:?} failed: {}", source, e);
+            return;
+        }
+    };
+    let mut seen: HashSet<String> = files.iter().cloned().collect();
+    for line in content.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        let file = line.to_string();
+        if !seen.insert(file.clone()) {
+            continue;
+        }
+        // `git diff --name-only` 中的其他文件直接忽略
+        if !line.to_ascii_lowercase().ends_with(".xml") {
+            debug!("skip: {:?}", file);
+            continue;
+        }
+        if is_virtual_path(line) || path::Path::new(line).is_file() {
+            debug!("file: {:?}", file);
+            files.push(file);
+        } else {
+            warn!("file {:?} listed in {:?} not found", line, source);
+        }
+    }
+}
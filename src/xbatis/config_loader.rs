@@ -0,0 +1,206 @@
+use super::def::*;
+use crate::scan::archive_scanner::*;
+use crate::scan::config_scanner::*;
+use crate::scan::text_decoder::*;
+use encoding_rs::Encoding;
+use lazy_static::*;
+use log::*;
+use regex::Regex;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::*;
+use xml::reader::*;
+
+lazy_static! {
+    /// `@Alias("name")` 注解
+    static ref ALIAS_RE: Regex = Regex::new("@Alias\\(\\s*\"([^\"]+)\"\\s*\\)").unwrap();
+}
+
+/// 默认的脚本语言
+const XML_LANGUAGE_DRIVERS: [&str; 2] = [
+    "xml",
+    "org.apache.ibatis.scripting.xmltags.XMLLanguageDriver",
+];
+
+/// 读取 `mybatis-config.xml` / `SqlMapConfig.xml` 中的 `<properties>` / `<typeAliases>` / `<settings>`
+pub fn load_config(
+    config_file: &String,
+    src_dirs: &Vec<String>,
+    encoding: Option<&'static Encoding>,
+) -> XBatisConfig {
+    let mut config = XBatisConfig::new();
+    config.encoding = encoding;
+    let content = match read_source_to_string(config_file, encoding) {
+        Ok(content) => content,
+        Err(e) => {
+            warn!("try to read config {:?} failed: {}", config_file, e);
+            return config;
+        }
+    };
+    let roots = collect_roots(config_file, src_dirs);
+    let mut in_properties = false;
+    let mut in_type_aliases = false;
+    let mut properties_file: Option<PathBuf> = None;
+    for e in create_xml_reader(&content) {
+        match e {
+            Ok(XmlEvent::StartElement {
+                name, attributes, ..
+            }) => {
+                let attr = |key: &str| {
+                    attributes
+                        .iter()
+                        .find(|attr| attr.name.local_name == key)
+                        .map(|attr| attr.value.trim().to_string())
+                };
+                match name.local_name.as_str() {
+                    "properties" => {
+                        in_properties = true;
+                        properties_file = match (attr("resource"), attr("url")) {
+                            (Some(resource), _) => {
+                                let path = resolve_resource(&roots, &resource);
+                                if path.is_none() {
+                                    warn!(
+                                        "properties {:?} referenced in {:?} not found",
+                                        resource, config_file
+                                    );
+                                }
+                                path
+                            }
+                            (None, Some(url)) => url_to_path(&url),
+                            _ => None,
+                        };
+                    }
+                    "property" if in_properties => {
+                        if let (Some(key), Some(value)) = (attr("name"), attr("value")) {
+                            config.properties.insert(key, value);
+                        }
+                    }
+                    "typeAliases" => in_type_aliases = true,
+                    "typeAlias" => {
+                        if let Some(type_name) = attr("type") {
+                            let alias = match attr("alias") {
+                                Some(alias) => alias,
+                                None => simple_name(&type_name),
+                            };
+                            config
+                                .type_aliases
+                                .insert(alias.to_ascii_lowercase(), type_name);
+                        }
+                    }
+                    "package" if in_type_aliases => {
+                        if let Some(package) = attr("name") {
+                            register_package(&mut config, &roots, &package);
+                        }
+                    }
+                    "setting" => {
+                        if let (Some(key), Some(value)) = (attr("name"), attr("value")) {
+                            config.settings.insert(key, value);
+                        }
+                    }
+                    "settings" => {
+                        // `iBATIS` 以属性声明设置
+                        for attr in &attributes {
+                            config
+                                .settings
+                                .insert(attr.name.local_name.clone(), attr.value.clone());
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            Ok(XmlEvent::EndElement { name }) => match name.local_name.as_str() {
+                "properties" => {
+                    in_properties = false;
+                    // 与 `MyBatis` 一致，外部文件中的属性覆盖内嵌的属性
+                    if let Some(path) = &properties_file {
+                        match fs::read_to_string(path) {
+                            Ok(content) => config.properties.extend(parse_properties(&content)),
+                            Err(e) => warn!("try to read {:?} failed: {}", path, e),
+                        }
+                    }
+                }
+                "typeAliases" => in_type_aliases = false,
+                _ => {}
+            },
+            Err(e) => {
+                warn!("try to parse config {:?} failed: {}", config_file, e);
+                break;
+            }
+            _ => {}
+        }
+    }
+    check_settings(&config);
+    info!(
+        "config loaded, properties: {:?}, type aliases: {:?}, settings: {:?}",
+        config.properties.len(),
+        config.type_aliases.len(),
+        config.settings.len()
+    );
+    config
+}
+
+/// 解析 `.properties` 文件，支持 `=` / `:` 分隔及 `#` / `!` 注释
+pub fn parse_properties(content: &str) -> HashMap<String, String> {
+    let mut properties = HashMap::new();
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with('!') {
+            continue;
+        }
+        let (key, value) = match line.find(['=', ':']) {
+            Some(i) => (&line[..i], &line[i + 1..]),
+            None => (line, ""),
+        };
+        properties.insert(key.trim().to_string(), value.trim().to_string());
+    }
+    properties
+}
+
+/// `<package name="...">`，以类名（或 `@Alias`）作为别名注册包及子包下的类
+fn register_package(config: &mut XBatisConfig, roots: &Vec<PathBuf>, package: &str) {
+    let dir = package.replace('.', "/");
+    for root in roots {
+        let package_dir = root.join(&dir);
+        if !package_dir.is_dir() {
+            continue;
+        }
+        for d in walkdir::WalkDir::new(&package_dir).into_iter().flatten() {
+            let path = d.path();
+            if !path.extension().is_some_and(|ext| ext.eq("java")) {
+                continue;
+            }
+            let relative = path.strip_prefix(&package_dir).unwrap().with_extension("");
+            let type_name =
+                package.to_string() + "." + &relative.to_string_lossy().replace(['/', '\\'], ".");
+            let alias = match fs::read_to_string(path)
+                .ok()
+                .and_then(|content| ALIAS_RE.captures(&content).map(|caps| caps[1].to_string()))
+            {
+                Some(alias) => alias,
+                None => simple_name(&type_name),
+            };
+            config
+                .type_aliases
+                .insert(alias.to_ascii_lowercase(), type_name);
+        }
+    }
+}
+
+fn simple_name(type_name: &str) -> String {
+    match type_name.rsplit_once('.') {
+        Some((_, name)) => name.to_string(),
+        None => type_name.to_string(),
+    }
+}
+
+/// 检查影响语句抽取的设置
+fn check_settings(config: &XBatisConfig) {
+    if let Some(lang) = config.settings.get("defaultScriptingLanguage") {
+        if !XML_LANGUAGE_DRIVERS.contains(&lang.as_str()) {
+            warn!(
+                "defaultScriptingLanguage is {:?}, statements are still extracted as XML scripts",
+                lang
+            );
+        }
+    }
+}
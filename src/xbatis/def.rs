@@ -1,12 +1,247 @@
-use regex::Regex;
+use encoding_rs::Encoding;
+use lazy_static::*;
+use regex::{Captures, Regex};
 use std::collections::HashMap;
+use std::path::PathBuf;
 use std::*;
 
+lazy_static! {
+    /// `${...}` 属性引用
+    static ref PROPERTY_RE: Regex = Regex::new("\\$\\{([^${}]+)\\}").unwrap();
+}
+
+/// 内置别名，与 `MyBatis` 的 `TypeAliasRegistry` 一致
+const BUILTIN_TYPE_ALIASES: &[(&str, &str)] = &[
+    ("string", "java.lang.String"),
+    ("byte", "java.lang.Byte"),
+    ("char", "java.lang.Character"),
+    ("character", "java.lang.Character"),
+    ("long", "java.lang.Long"),
+    ("short", "java.lang.Short"),
+    ("int", "java.lang.Integer"),
+    ("integer", "java.lang.Integer"),
+    ("double", "java.lang.Double"),
+    ("float", "java.lang.Float"),
+    ("boolean", "java.lang.Boolean"),
+    ("_byte", "byte"),
+    ("_char", "char"),
+    ("_character", "char"),
+    ("_long", "long"),
+    ("_short", "short"),
+    ("_int", "int"),
+    ("_integer", "int"),
+    ("_double", "double"),
+    ("_float", "float"),
+    ("_boolean", "boolean"),
+    ("date", "java.util.Date"),
+    ("decimal", "java.math.BigDecimal"),
+    ("bigdecimal", "java.math.BigDecimal"),
+    ("biginteger", "java.math.BigInteger"),
+    ("object", "java.lang.Object"),
+    ("map", "java.util.Map"),
+    ("hashmap", "java.util.HashMap"),
+    ("list", "java.util.List"),
+    ("arraylist", "java.util.ArrayList"),
+    ("collection", "java.util.Collection"),
+    ("iterator", "java.util.Iterator"),
+    ("resultset", "java.sql.ResultSet"),
+];
+
+/// 开启 `${key:default}` 默认值的属性
+const ENABLE_DEFAULT_VALUE: &str = "org.apache.ibatis.parsing.PropertyParser.enable-default-value";
+/// 默认值分隔符的属性
+const DEFAULT_VALUE_SEPARATOR: &str =
+    "org.apache.ibatis.parsing.PropertyParser.default-value-separator";
+
+/// 全局配置，来自 `mybatis-config.xml` / `SqlMapConfig.xml`
+pub struct XBatisConfig {
+    /// `<properties>`，用于替换 `${...}`
+    pub properties: HashMap<String, String>,
+    /// `<typeAliases>`，别名均为小写
+    pub type_aliases: HashMap<String, String>,
+    /// `<settings>`
+    pub settings: HashMap<String, String>,
+    /// `--encoding` 指定的编码，覆盖 `xml` 声明，`BOM` 仍优先
+    pub encoding: Option<&'static Encoding>,
+    /// 源目录，已规范化；外部实体只读取映射文件所在目录或这些目录之内的文件
+    pub source_roots: Vec<PathBuf>,
+    /// 是否读取归档中的映射文件引用的外部实体，仅限同一归档中映射文件所在目录之内
+    pub archive_entities: bool,
+}
+
+impl XBatisConfig {
+    /// 仅含内置别名
+    pub fn new() -> Self {
+        let mut type_aliases = HashMap::new();
+        for (alias, type_name) in BUILTIN_TYPE_ALIASES {
+            type_aliases.insert(alias.to_string(), type_name.to_string());
+            type_aliases.insert(alias.to_string() + "[]", type_name.to_string() + "[]");
+        }
+        XBatisConfig {
+            properties: HashMap::new(),
+            type_aliases,
+            settings: HashMap::new(),
+            encoding: None,
+            source_roots: Vec::new(),
+            archive_entities: false,
+        }
+    }
+
+    /// 按别名解析类型，别名不区分大小写，未定义时原样返回
+    pub fn resolve_type(&self, name: &str) -> String {
+        match self.type_aliases.get(&name.to_ascii_lowercase()) {
+            Some(type_name) => type_name.clone(),
+            None => name.to_string(),
+        }
+    }
+
+    /// 与 `MyBatis` 启动时相同，用属性替换 `${key}`，未定义的保持原样
+    pub fn replace_properties(&self, text: &str) -> String {
+        if self.properties.is_empty() || !text.contains("${") {
+            return text.to_string();
+        }
+        let enable_default = self
+            .properties
+            .get(ENABLE_DEFAULT_VALUE)
+            .is_some_and(|v| v == "true");
+        let separator = match self.properties.get(DEFAULT_VALUE_SEPARATOR) {
+            Some(separator) => separator.as_str(),
+            None => ":",
+        };
+        PROPERTY_RE
+            .replace_all(text, |caps: &Captures| {
+                let key = &caps[1];
+                if let Some(value) = self.properties.get(key) {
+                    return value.clone();
+                }
+                if enable_default {
+                    if let Some((key, default)) = key.split_once(separator) {
+                        return match self.properties.get(key) {
+                            Some(value) => value.clone(),
+                            None => default.to_string(),
+                        };
+                    }
+                }
+                caps[0].to_string()
+            })
+            .to_string()
+    }
+
+    /// 与 `MyBatis` 按 `resultType` 自动映射相同，列标签对应到属性，
+    /// 开启 `mapUnderscoreToCamelCase` 时去掉下划线转为驼峰；`java.` 下的类型如 `Map`、`Integer` 不按属性映射
+    pub fn result_mappings(&self, result_type: &str, labels: &[String]) -> Vec<ResultMapping> {
+        if result_type.is_empty() || result_type.starts_with("java.") {
+            return Vec::new();
+        }
+        let camel_case = self
+            .settings
+            .get("mapUnderscoreToCamelCase")
+            .is_some_and(|v| v == "true");
+        labels
+            .iter()
+            .map(|label| ResultMapping {
+                column: label.clone(),
+                property: property_name(label, camel_case),
+            })
+            .collect()
+    }
+}
+
+/// 列标签对应的属性名，未加引号的大写标签转为小写
+fn property_name(label: &str, camel_case: bool) -> String {
+    let label = if label.chars().any(|c| c.is_lowercase()) {
+        label.to_string()
+    } else {
+        label.to_lowercase()
+    };
+    if !camel_case {
+        return label;
+    }
+    let mut property = String::with_capacity(label.len());
+    let mut upper_next = false;
+    for c in label.chars() {
+        if c == '_' {
+            upper_next = !property.is_empty();
+        } else if upper_next {
+            property.extend(c.to_uppercase());
+            upper_next = false;
+        } else {
+            property.push(c);
+        }
+    }
+    property
+}
+
 pub enum DialectType {
     Oracle,
     MySQL,
+    PostgreSQL,
+    SQLServer,
+    DB2,
+    H2,
+    SQLite,
+    /// 达梦，兼容 `Oracle`
+    DM,
+    /// 人大金仓，兼容 `PostgreSQL`
+    KingbaseES,
+    /// `OceanBase` 的 `Oracle` 模式
+    OceanBaseOracle,
+    /// `OceanBase` 的 `MySQL` 模式
+    OceanBaseMySQL,
+    /// `openGauss` / `GaussDB`，兼容 `PostgreSQL`
+    GaussDB,
 }
 
+impl DialectType {
+    /// 方言默认的占位符样式
+    pub fn placeholder_style(&self) -> PlaceholderStyle {
+        match self {
+            DialectType::Oracle | DialectType::DM | DialectType::OceanBaseOracle => {
+                PlaceholderStyle::Fixed(":?")
+            }
+            DialectType::MySQL | DialectType::OceanBaseMySQL => PlaceholderStyle::Fixed("@1"),
+            DialectType::PostgreSQL | DialectType::KingbaseES | DialectType::GaussDB => {
+                PlaceholderStyle::Indexed("$")
+            }
+            DialectType::SQLServer => PlaceholderStyle::Indexed("@p"),
+            DialectType::DB2 => PlaceholderStyle::Fixed("?"),
+            DialectType::H2 | DialectType::SQLite => PlaceholderStyle::Indexed("?"),
+        }
+    }
+
+    /// 按方言为标识符加引号
+    pub fn quote_identifier(&self, name: &str) -> String {
+        match self {
+            DialectType::MySQL | DialectType::OceanBaseMySQL => format!("`{}`", name),
+            DialectType::SQLServer => format!("[{}]", name),
+            _ => format!("\"{}\"", name),
+        }
+    }
+}
+
+/// 占位符样式
+pub enum PlaceholderStyle {
+    /// 固定文本，如 `?` / `:?` / `@1`
+    Fixed(&'static str),
+    /// 前缀加序号（从 1 开始），如 `$1` / `@p1` / `?1` / `:1`
+    Indexed(&'static str),
+    /// 以参数名命名，如 `#{user.id}` 为 `:user_id`
+    Named,
+    /// 保留原始参数文本，如 `#{user.id}`
+    Keep,
+}
+
+/// 语句整理方式
+pub enum NormalizeMode {
+    /// 不整理，保留原始语句
+    None,
+    /// 去掉行注释并合并空白
+    Whitespace,
+    /// 完整整理：关键字转大写、替换占位符、修正动态语句的残留
+    Full,
+}
+
+#[derive(Clone, Copy)]
 pub enum Mode {
     Statement,
     Select,
@@ -30,6 +265,66 @@ impl Mode {
             _ => panic!("unkown mode"),
         }
     }
+
+    /// 语句类型名称
+    pub fn name(&self) -> &str {
+        match self {
+            Mode::Statement => "statement",
+            Mode::Select => "select",
+            Mode::Insert => "insert",
+            Mode::Update => "update",
+            Mode::Delete => "delete",
+            Mode::SelectKey => "selectKey",
+            Mode::SqlPart => "sql",
+        }
+    }
+}
+
+/// 源文件中的位置，行号、列号均从 1 开始
+#[derive(Clone, Copy, Default)]
+pub struct SourcePos {
+    pub line: u32,
+    pub column: u32,
+}
+
+impl SourcePos {
+    /// `file:line`，便于在 IDE 或 CI 中跳转
+    pub fn location(&self, filename: &String) -> String {
+        format!("{}:{}", filename, self.line)
+    }
+}
+
+/// 文件解析失败的位置及原因
+#[derive(Clone)]
+pub struct XmlParseError {
+    pub pos: SourcePos,
+    pub message: String,
+}
+
+impl XmlParseError {
+    /// `file:line:column: message`
+    pub fn describe(&self, filename: &String) -> String {
+        format!(
+            "{}:{}: {}",
+            self.pos.location(filename),
+            self.pos.column,
+            self.message
+        )
+    }
+}
+
+/// 源文件中的范围，起于开始标签，止于结束标签
+#[derive(Clone, Copy, Default)]
+pub struct SourceSpan {
+    pub start: SourcePos,
+    pub end: SourcePos,
+}
+
+/// 引用子句处，`<include refid="..."/>`
+#[derive(Clone)]
+pub struct IncludeSite {
+    pub refid: String,
+    pub pos: SourcePos,
 }
 
 pub struct SqlKey {
@@ -37,14 +332,20 @@ pub struct SqlKey {
     pub key: String,
     /// 键语句
     pub sql: String,
+    /// 原始 `xml` 片段
+    pub xml: String,
+    /// 所在位置
+    pub span: SourceSpan,
 }
 
 impl SqlKey {
     pub fn empty() -> SqlKey {
-        return SqlKey {
+        SqlKey {
             key: String::from(""),
             sql: String::from(""),
-        };
+            xml: String::from(""),
+            span: SourceSpan::default(),
+        }
     }
 }
 
@@ -56,6 +357,16 @@ pub struct SqlStatement {
     pub include_keys: Vec<String>,
     pub has_sql_key: bool,
     pub sql_key: SqlKey,
+    /// 原始 `xml` 片段
+    pub xml: String,
+    /// 所在位置
+    pub span: SourceSpan,
+    /// 引用子句处
+    pub include_sites: Vec<IncludeSite>,
+    /// 参数类型，已按别名解析
+    pub parameter_type: String,
+    /// 结果类型，已按别名解析
+    pub result_type: String,
 }
 
 impl SqlStatement {
@@ -68,7 +379,7 @@ impl SqlStatement {
         has_sql_key: bool,
         sql_key: SqlKey,
     ) -> Self {
-        return SqlStatement {
+        SqlStatement {
             mode,
             id,
             sql,
@@ -76,7 +387,12 @@ impl SqlStatement {
             include_keys,
             has_sql_key,
             sql_key,
-        };
+            xml: String::from(""),
+            span: SourceSpan::default(),
+            include_sites: Vec::new(),
+            parameter_type: String::from(""),
+            result_type: String::from(""),
+        }
     }
 }
 
@@ -102,6 +418,20 @@ pub struct XmlParsedState {
     pub include_keys: Vec<String>,
     /// 循环定义
     pub loop_def: LoopDef,
+    /// 当前事件所在位置
+    pub pos: SourcePos,
+    /// 当前语句开始位置
+    pub start_pos: SourcePos,
+    /// 取键语句开始位置
+    pub key_start_pos: SourcePos,
+    /// 取键语句结束位置
+    pub key_end_pos: SourcePos,
+    /// 引用子句处
+    pub include_sites: Vec<IncludeSite>,
+    /// 当前语句的参数类型
+    pub parameter_type: String,
+    /// 当前语句的结果类型
+    pub result_type: String,
 
     /// 过程中累计
 
@@ -118,12 +448,16 @@ pub struct XmlParsedState {
 
     /// 文件名
     pub filename: String,
+    /// 命名空间
+    pub namespace: String,
+    /// 文件内容
+    pub content: String,
 }
 
 impl XmlParsedState {
     /// 构建器，构造工厂
     pub fn new() -> Self {
-        return XmlParsedState {
+        XmlParsedState {
             in_statement: false,
             in_sql_key: false,
             in_loop: false,
@@ -138,10 +472,19 @@ impl XmlParsedState {
                 suffix: String::from(""),
                 separator: String::from(""),
             },
+            pos: SourcePos::default(),
+            start_pos: SourcePos::default(),
+            key_start_pos: SourcePos::default(),
+            key_end_pos: SourcePos::default(),
+            include_sites: Vec::new(),
+            parameter_type: String::from(""),
+            result_type: String::from(""),
             statements: Vec::new(),
             sql_part_map: HashMap::new(),
             filename: String::from(""),
-        };
+            namespace: String::from(""),
+            content: String::from(""),
+        }
     }
 
     pub fn reset(&mut self) {
@@ -153,6 +496,9 @@ impl XmlParsedState {
         self.current_id = String::from("");
         self.current_key_id = String::from("");
         self.include_keys = Vec::new();
+        self.include_sites = Vec::new();
+        self.parameter_type = String::from("");
+        self.result_type = String::from("");
         self.loop_def = LoopDef {
             suffix: String::from(""),
             separator: String::from(""),
@@ -162,17 +508,160 @@ impl XmlParsedState {
     }
 }
 
+/// 整理后的语句
+pub struct SqlResult {
+    /// 语句类型
+    pub mode: Mode,
+    /// 语句ID
+    pub id: String,
+    /// 整理后的语句
+    pub sql: String,
+    /// 整理后的语句中占位符及文本替换标记的字节区间
+    pub marked_spans: Vec<ops::Range<usize>>,
+    /// 合并子句后的原始语句
+    pub raw_sql: String,
+    /// 参数，按出现顺序
+    pub params: Vec<SqlParam>,
+    /// 直接拼接的文本替换，按出现顺序
+    pub substitutions: Vec<String>,
+    /// 涉及的表及列
+    pub table_refs: Vec<TableRef>,
+    /// 涉及多个表时无法确定所属表的列
+    pub unresolved_columns: Vec<String>,
+    /// 原始 `xml` 片段
+    pub xml: String,
+    /// 所在位置
+    pub span: SourceSpan,
+    /// 引用子句处
+    pub include_sites: Vec<IncludeSite>,
+    /// 参数类型，已按别名解析
+    pub parameter_type: String,
+    /// 结果类型，已按别名解析
+    pub result_type: String,
+    /// 按结果类型自动映射的列与属性
+    pub result_mappings: Vec<ResultMapping>,
+}
+
+/// 表的访问方式
+#[derive(Clone, Copy, PartialEq)]
+pub enum TableAccess {
+    Select,
+    Insert,
+    Update,
+    Delete,
+    Merge,
+}
+
+impl TableAccess {
+    pub fn name(&self) -> &str {
+        match self {
+            TableAccess::Select => "SELECT",
+            TableAccess::Insert => "INSERT",
+            TableAccess::Update => "UPDATE",
+            TableAccess::Delete => "DELETE",
+            TableAccess::Merge => "MERGE",
+        }
+    }
+}
+
+/// 语句中引用的表，未加引号的名称均为大写，加引号的保持原样
+#[derive(Clone)]
+pub struct TableRef {
+    /// 表名，可含模式名，如 `__REPLACE_SCHEMA__.USERS`
+    pub table: String,
+    /// 访问方式，按出现顺序
+    pub accesses: Vec<TableAccess>,
+    /// 别名
+    pub aliases: Vec<String>,
+    /// 引用的列，`*` 表示全部列
+    pub columns: Vec<String>,
+}
+
+impl TableRef {
+    /// `SELECT, UPDATE`
+    pub fn access_names(&self) -> String {
+        self.accesses
+            .iter()
+            .map(|access| access.name())
+            .collect::<Vec<&str>>()
+            .join(", ")
+    }
+}
+
+/// 参数，未声明的属性为空串
+pub struct SqlParam {
+    /// 原始文本，如 `#{id,jdbcType=VARCHAR}`
+    pub text: String,
+    /// 属性路径，如 `user.id`
+    pub property: String,
+    pub jdbc_type: String,
+    pub java_type: String,
+    /// `IN` / `OUT` / `INOUT`
+    pub mode: String,
+    pub type_handler: String,
+    pub numeric_scale: String,
+}
+
+/// `resultType` 自动映射的列与属性
+pub struct ResultMapping {
+    /// 列标签，列名或别名
+    pub column: String,
+    /// 属性名
+    pub property: String,
+}
+
+/// 单个文件的解析结果
+pub struct XmlParsedFile {
+    /// 文件名
+    pub filename: String,
+    /// 内容相同的其他路径
+    pub duplicates: Vec<String>,
+    /// 命名空间
+    pub namespace: String,
+    /// 语句集
+    pub results: Vec<SqlResult>,
+    /// 子句集
+    pub sql_part_map: HashMap<String, SqlStatement>,
+    /// 解析失败时的错误，此时只含出错前已完整读取的语句
+    pub error: Option<XmlParseError>,
+}
+
 pub struct RegexReplacement {
     pub regex: Regex,
     pub target: String,
+    /// 是否替换为占位符，占位符由方言按出现顺序生成
+    pub is_placeholder: bool,
+    /// 是否记录替换结果的位置，用于在报告中标出
+    pub is_marked: bool,
 }
 
 impl RegexReplacement {
     pub fn new(regex: &str, target: &str) -> Self {
-        return RegexReplacement {
+        RegexReplacement {
             regex: Regex::new(regex).unwrap(),
             target: String::from(target),
-        };
+            is_placeholder: false,
+            is_marked: false,
+        }
+    }
+
+    /// 替换为文本替换的标记，并记录位置
+    pub fn marker(regex: &str, target: &str) -> Self {
+        RegexReplacement {
+            regex: Regex::new(regex).unwrap(),
+            target: String::from(target),
+            is_placeholder: false,
+            is_marked: true,
+        }
+    }
+
+    pub fn placeholder(regex: &str) -> Self {
+        RegexReplacement {
+            regex: Regex::new(regex).unwrap(),
+            target: String::from(""),
+            is_placeholder: true,
+            is_marked: true,
+        }
     }
 }
 
@@ -0,0 +1,286 @@
+use super::def::*;
+use crate::scan::archive_scanner::*;
+use crate::scan::config_scanner::*;
+use lazy_static::*;
+use log::*;
+use regex::{Captures, Regex};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::*;
+
+lazy_static! {
+    /// `<!DOCTYPE mapper PUBLIC "..." "..." [ ... ]>`，依次为公共标识、系统标识及内部子集
+    static ref DOCTYPE_RE: Regex = Regex::new(
+        "(?s)<!DOCTYPE\\s+[^\\s\\[>]+(?:\\s+(?:PUBLIC\\s+(?:\"([^\"]*)\"|'([^']*)')\\s+|SYSTEM\\s+)(?:\"([^\"]*)\"|'([^']*)'))?\\s*(?:\\[(.*?)\\]\\s*)?>",
+    ).unwrap();
+    /// `<!ENTITY name "value">` / `<!ENTITY name SYSTEM "file">`，参数实体（`%`）不处理
+    static ref ENTITY_RE: Regex = Regex::new(
+        "<!ENTITY\\s+(%\\s+)?([^\\s%\"']+)\\s+(?:\"([^\"]*)\"|'([^']*)'|(?:SYSTEM|PUBLIC\\s+(?:\"[^\"]*\"|'[^']*'))\\s+(?:\"([^\"]*)\"|'([^']*)'))[^>]*>",
+    ).unwrap();
+    /// 注释
+    static ref COMMENT_RE: Regex = Regex::new("(?s)<!--.*?-->").unwrap();
+}
+
+/// 内置的 `DTD`
+struct BundledDtd {
+    public_ids: &'static [&'static str],
+    /// 去掉协议后的系统标识
+    system_ids: &'static [&'static str],
+    content: &'static str,
+}
+
+const BUNDLED_DTDS: [BundledDtd; 4] = [
+    BundledDtd {
+        public_ids: &["-//mybatis.org//DTD Mapper 3.0//EN"],
+        system_ids: &["mybatis.org/dtd/mybatis-3-mapper.dtd"],
+        content: include_str!("dtd/mybatis-3-mapper.dtd"),
+    },
+    BundledDtd {
+        public_ids: &["-//mybatis.org//DTD Config 3.0//EN"],
+        system_ids: &["mybatis.org/dtd/mybatis-3-config.dtd"],
+        content: include_str!("dtd/mybatis-3-config.dtd"),
+    },
+    BundledDtd {
+        public_ids: &[
+            "-//ibatis.apache.org//DTD SQL Map 2.0//EN",
+            "-//iBATIS.com//DTD SQL Map 2.0//EN",
+        ],
+        system_ids: &[
+            "ibatis.apache.org/dtd/sql-map-2.dtd",
+            "www.ibatis.com/dtd/sql-map-2.dtd",
+        ],
+        content: include_str!("dtd/sql-map-2.dtd"),
+    },
+    BundledDtd {
+        public_ids: &[
+            "-//ibatis.apache.org//DTD SQL Map Config 2.0//EN",
+            "-//iBATIS.com//DTD SQL Map Config 2.0//EN",
+        ],
+        system_ids: &[
+            "ibatis.apache.org/dtd/sql-map-config-2.dtd",
+            "www.ibatis.com/dtd/sql-map-config-2.dtd",
+        ],
+        content: include_str!("dtd/sql-map-config-2.dtd"),
+    },
+];
+
+/// 处理文档类型声明，从不访问网络：
+/// 外部 `DTD` 使用本地文件或内置副本，其中的实体补入内部子集；
+/// 内部子集中的外部实体（`SYSTEM`）读取本地文件后改写为内部实体。
+/// 改写时换行以字符引用表示，其后各行的行号不变
+pub fn resolve_doctype(content: &str, filename: &String, config: &XBatisConfig) -> String {
+    let caps = match DOCTYPE_RE.captures(content) {
+        Some(caps) => caps,
+        None => return content.to_string(),
+    };
+    let public_id = caps.get(1).or(caps.get(2)).map(|m| m.as_str());
+    let system_id = caps.get(3).or(caps.get(4)).map(|m| m.as_str());
+    let subset = caps.get(5);
+    let mut declared: HashSet<String> = HashSet::new();
+    let mut new_subset = String::from("");
+    if let Some(subset) = subset {
+        new_subset = ENTITY_RE
+            .replace_all(subset.as_str(), |entity: &Captures| {
+                if entity.get(1).is_some() {
+                    return entity[0].to_string();
+                }
+                let name = entity[2].to_string();
+                declared.insert(name.clone());
+                match entity.get(5).or(entity.get(6)) {
+                    Some(system_id) => {
+                        match read_external(system_id.as_str(), filename, config) {
+                            // 保留原声明中的换行
+                            Ok(value) => {
+                                entity_decl(&name, &value)
+                                    + &"\n".repeat(entity[0].matches('\n').count())
+                            }
+                            Err(reason) => {
+                                warn!(
+                                    "external entity {:?} -> {:?} in {:?} is not resolved ({}), expanded as empty",
+                                    name,
+                                    system_id.as_str(),
+                                    filename,
+                                    reason
+                                );
+                                entity[0].to_string()
+                            }
+                        }
+                    }
+                    None => entity[0].to_string(),
+                }
+            })
+            .to_string();
+    }
+    // 内部子集中的声明优先，外部 `DTD` 中同名的实体忽略
+    let mut additions = String::from("");
+    if let Some(dtd) = load_dtd(public_id, system_id, filename, config) {
+        for (name, value) in collect_entities(&dtd) {
+            if declared.insert(name.clone()) {
+                additions += &literal_decl(&name, &value);
+            }
+        }
+    }
+    let whole = caps.get(0).unwrap();
+    let changed = match subset {
+        Some(subset) => new_subset != subset.as_str() || !additions.is_empty(),
+        None => !additions.is_empty(),
+    };
+    if !changed {
+        return content.to_string();
+    }
+    match subset {
+        Some(subset) => {
+            content[..subset.start()].to_string()
+                + &new_subset
+                + &additions
+                + &content[subset.end()..]
+        }
+        None => {
+            content[..whole.end() - 1].to_string()
+                + " ["
+                + &additions
+                + "]"
+                + &content[whole.end() - 1..]
+        }
+    }
+}
+
+/// 外部 `DTD`：优先使用本地文件（相对于映射文件），其次按公共标识或系统标识使用内置副本，
+/// 其他远程的 `DTD` 直接跳过
+fn load_dtd(
+    public_id: Option<&str>,
+    system_id: Option<&str>,
+    filename: &String,
+    config: &XBatisConfig,
+) -> Option<String> {
+    let system_id = system_id?;
+    let bundled = BUNDLED_DTDS.iter().find(|dtd| {
+        public_id.is_some_and(|id| dtd.public_ids.contains(&id))
+            || dtd.system_ids.contains(&strip_scheme(system_id))
+    });
+    match (read_external(system_id, filename, config), bundled) {
+        (Ok(dtd), _) => {
+            debug!("use local dtd {:?} for {:?}", system_id, filename);
+            Some(dtd)
+        }
+        (Err(_), Some(dtd)) => {
+            debug!("use bundled dtd {:?} for {:?}", system_id, filename);
+            Some(dtd.content.to_string())
+        }
+        (Err(reason), None) if system_id.contains("://") && !system_id.starts_with("file:") => {
+            debug!("skip dtd {:?} of {:?}: {}", system_id, filename, reason);
+            None
+        }
+        (Err(reason), None) => {
+            warn!(
+                "dtd {:?} of {:?} is not resolved ({}), entities declared in it are unavailable",
+                system_id, filename, reason
+            );
+            None
+        }
+    }
+}
+
+/// 读取本地的外部资源，失败时返回原因：
+/// 只读取映射文件所在目录或源目录之内的文件，以免将其他文件的内容带入输出；
+/// 归档中的映射文件仅在 `--archive-entities` 时读取同一归档中、映射文件所在目录之内的文件；
+/// `http(s)://` 等远程资源不读取
+fn read_external(
+    system_id: &str,
+    filename: &String,
+    config: &XBatisConfig,
+) -> result::Result<String, &'static str> {
+    if system_id.contains("://") && !system_id.starts_with("file:") {
+        return Err("remote resources are not fetched");
+    }
+    let path = match split_virtual_path(filename).last() {
+        Some(entry) if is_virtual_path(filename) => {
+            if !config.archive_entities {
+                return Err("entities of mappers in archives need --archive-entities");
+            }
+            let archive = &filename[..filename.len() - entry.len()];
+            let dir = entry.rfind('/').map(|j| &entry[..j]).unwrap_or("");
+            match join_entry(dir, system_id) {
+                Some(entry) => archive.to_string() + &entry,
+                None => return Err("outside the directory of the mapper"),
+            }
+        }
+        _ => {
+            let base = Path::new(filename).parent().unwrap_or(Path::new(""));
+            let path = if system_id.starts_with("file:") {
+                url_to_path(system_id).ok_or("unsupported url")?
+            } else {
+                base.join(system_id)
+            };
+            // 规范化后比较，`../` 及符号链接均无法越出
+            let real = fs::canonicalize(&path).map_err(|_| "not found")?;
+            let inside = fs::canonicalize(base)
+                .into_iter()
+                .chain(config.source_roots.iter().cloned())
+                .any(|root: PathBuf| real.starts_with(root));
+            if !inside {
+                return Err("outside the directory of the mapper and the source directories");
+            }
+            real.to_string_lossy().to_string()
+        }
+    };
+    read_source_to_string(&path, config.encoding).map_err(|_| "not found")
+}
+
+/// 归档中相对于 `dir` 的路径，越出 `dir` 或为绝对路径时返回 `None`
+fn join_entry(dir: &str, relative: &str) -> Option<String> {
+    if relative.starts_with('/') || relative.starts_with("file:") {
+        return None;
+    }
+    let base: Vec<&str> = dir.split('/').filter(|part| !part.is_empty()).collect();
+    let mut parts = base.clone();
+    for part in relative.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                if parts.len() <= base.len() {
+                    return None;
+                }
+                parts.pop();
+            }
+            _ => parts.push(part),
+        }
+    }
+    Some(parts.join("/"))
+}
+
+/// `DTD` 中以字面量声明的一般实体
+fn collect_entities(dtd: &str) -> Vec<(String, String)> {
+    let dtd = COMMENT_RE.replace_all(dtd, "");
+    ENTITY_RE
+        .captures_iter(&dtd)
+        .filter(|entity| entity.get(1).is_none())
+        .filter_map(|entity| {
+            let value = entity.get(3).or(entity.get(4))?;
+            Some((entity[2].to_string(), value.as_str().to_string()))
+        })
+        .collect()
+}
+
+/// 以外部文件的内容声明内部实体，`&` / `%` 转为字符引用
+fn entity_decl(name: &str, value: &str) -> String {
+    let value = value.replace('&', "&#38;").replace('%', "&#37;");
+    literal_decl(name, &value)
+}
+
+/// 以字面量声明内部实体，引号及换行转为字符引用
+fn literal_decl(name: &str, value: &str) -> String {
+    let value = value
+        .replace('"', "&#34;")
+        .replace('\r', "&#13;")
+        .replace('\n', "&#10;");
+    format!("<!ENTITY {} \"{}\">", name, value)
+}
+
+/// 去掉系统标识中的协议（`http://` 等）
+fn strip_scheme(system_id: &str) -> &str {
+    match system_id.find("://") {
+        Some(i) => &system_id[i + 3..],
+        None => system_id,
+    }
+}
@@ -8,41 +8,48 @@ use xml::name::*;
 
 lazy_static! {
     static ref RE: Regex = Regex::new("DTD SQL Map 2\\.0").unwrap();
+    static ref PARAM_RE: Regex = Regex::new("#[^#]+#").unwrap();
+    static ref SUBSTITUTION_RE: Regex = Regex::new("\\$[^${}\\s]+\\$").unwrap();
 }
 
 /// `iBATIS` 实现
-pub fn create_ibatis_parser(dialect_type: DialectType) -> IBatisParser {
+pub fn create_ibatis_parser(
+    dialect_type: DialectType,
+    normalize_mode: NormalizeMode,
+    placeholder_style: PlaceholderStyle,
+    config: XBatisConfig,
+) -> IBatisParser {
     let re_vec;
     {
-        re_vec = create_replcements(&dialect_type);
+        re_vec = create_replcements();
     }
-    return IBatisParser {
+    IBatisParser {
         dialect_type,
-        re_vec: re_vec,
-    };
+        normalize_mode,
+        placeholder_style,
+        config,
+        re_vec,
+    }
 }
 
-fn create_replcements(dialect_type: &DialectType) -> Vec<RegexReplacement> {
-    let placeholder = match dialect_type {
-        DialectType::Oracle => ":?",
-        DialectType::MySQL => "@1",
-    };
-    return vec![
-        RegexReplacement::new("[\t ]?--[^\n]*\n", " "),
-        RegexReplacement::new("[\r\n\t ]+", " "),
-        RegexReplacement::new("\\$\\{[^${]+\\}", "__REPLACE_SCHEMA__"),
-        RegexReplacement::new("#[^#]+#", placeholder),
-        RegexReplacement::new("\\$[^$]+\\$", placeholder),
+fn create_replcements() -> Vec<RegexReplacement> {
+    vec![
+        RegexReplacement::marker("\\$\\{[^${}]+\\}", "__REPLACE_SCHEMA__"),
+        RegexReplacement::marker("\\$[^$\\s]+\\$", "/*${0}*/"),
+        RegexReplacement::placeholder("#[^#]+#"),
         RegexReplacement::new("WHERE[ ]+AND[ ]+", "WHERE "),
         RegexReplacement::new("WHERE[ ]+OR[ ]+", "WHERE "),
         RegexReplacement::new(",[ ]+WHERE", " WHERE"),
         RegexReplacement::new("[ ]*,[ ]*\\)", ")"),
         RegexReplacement::new(",$", ""),
-    ];
+    ]
 }
 
 pub struct IBatisParser {
     dialect_type: DialectType,
+    normalize_mode: NormalizeMode,
+    placeholder_style: PlaceholderStyle,
+    config: XBatisConfig,
     re_vec: Vec<RegexReplacement>,
 }
 
@@ -51,15 +58,31 @@ impl Parser for IBatisParser {
         self.dialect_type = dialect_type;
     }
 
-    fn detect_match(&self, file: &String) -> bool {
-        return self.detect_match_with_regex(file, &RE);
+    fn dialect_type(&self) -> &DialectType {
+        &self.dialect_type
+    }
+
+    fn placeholder_style(&self) -> &PlaceholderStyle {
+        &self.placeholder_style
+    }
+
+    fn normalize_mode(&self) -> &NormalizeMode {
+        &self.normalize_mode
+    }
+
+    fn config(&self) -> &XBatisConfig {
+        &self.config
+    }
+
+    fn detect_content(&self, content: &str) -> bool {
+        RE.is_match(content)
     }
 
     fn ex_parse_start_element(
         &self,
         _name: OwnedName,
-        _element_name: &String,
-        attributes: &Vec<OwnedAttribute>,
+        _element_name: &str,
+        attributes: &[OwnedAttribute],
         state: &mut XmlParsedState,
     ) {
         if state.in_statement {
@@ -74,12 +97,20 @@ impl Parser for IBatisParser {
     fn ex_parse_end_element(
         &self,
         _name: OwnedName,
-        _element_name: &String,
+        _element_name: &str,
         _state: &mut XmlParsedState,
     ) {
     }
 
-    fn clear_and_push(&self, sql_store: &mut Vec<String>, origin_sql: &String) {
-        self.loop_clear_and_push(sql_store, &self.re_vec, origin_sql)
+    fn collect_params(&self, origin_sql: &str) -> Vec<SqlParam> {
+        collect_params_with(&PARAM_RE, origin_sql)
+    }
+
+    fn collect_substitutions(&self, origin_sql: &str) -> Vec<String> {
+        collect_matched(&SUBSTITUTION_RE, origin_sql)
+    }
+
+    fn clear(&self, origin_sql: &str) -> String {
+        self.loop_clear(&self.re_vec, origin_sql)
     }
 }
@@ -1,10 +1,18 @@
+/// 读取全局配置
+pub mod config_loader;
 /// 供解析器使用的内部定义
 pub mod def;
+/// 离线处理文档类型声明及实体
+pub mod dtd_resolver;
 /// iBATIS 解析器
 pub mod ibatis_parser;
 /// MyBatis 解析器
 pub mod mybatis_parser;
 /// 供解析器使用的工具方法
 mod parse_helper;
+/// 分析语句涉及的表及列
+pub mod sql_analyzer;
+/// `sql` 分词器
+pub mod sql_tokenizer;
 /// 抽象解析器
 pub mod xbatis_parser;
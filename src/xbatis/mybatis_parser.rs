@@ -1,5 +1,5 @@
 use super::def::*;
-use super::parse_helper::search_matched_attr;
+use super::parse_helper::*;
 use super::xbatis_parser::*;
 use lazy_static::*;
 use regex::Regex;
@@ -8,40 +8,47 @@ use xml::name::*;
 
 lazy_static! {
     static ref RE: Regex = Regex::new("DTD Mapper 3\\.0").unwrap();
+    static ref PARAM_RE: Regex = Regex::new("#\\{[^#{}]+\\}").unwrap();
+    static ref SUBSTITUTION_RE: Regex = Regex::new("\\$\\{[^${}]+\\}").unwrap();
 }
 
 /// `MyBatis` 实现
-pub fn create_mybatis_parser(dialect_type: DialectType) -> MyBatisParser {
+pub fn create_mybatis_parser(
+    dialect_type: DialectType,
+    normalize_mode: NormalizeMode,
+    placeholder_style: PlaceholderStyle,
+    config: XBatisConfig,
+) -> MyBatisParser {
     let re_vec;
     {
-        re_vec = create_replcements(&dialect_type);
+        re_vec = create_replcements();
     }
-    return MyBatisParser {
+    MyBatisParser {
         dialect_type,
-        re_vec: re_vec,
-    };
+        normalize_mode,
+        placeholder_style,
+        config,
+        re_vec,
+    }
 }
 
-fn create_replcements(dialect_type: &DialectType) -> Vec<RegexReplacement> {
-    let placeholder = match dialect_type {
-        DialectType::Oracle => ":?",
-        DialectType::MySQL => "@1",
-    };
-    return vec![
-        RegexReplacement::new("[\t ]?--[^\n]*\n", ""),
-        RegexReplacement::new("[\r\n\t ]+", " "),
-        RegexReplacement::new("#\\{[^#{]+\\}", placeholder),
-        RegexReplacement::new("\\$\\{[^${]+\\}", placeholder),
+fn create_replcements() -> Vec<RegexReplacement> {
+    vec![
+        RegexReplacement::marker("\\$\\{[^${}]+\\}", "/*${0}*/"),
+        RegexReplacement::placeholder("#\\{[^#{}]+\\}"),
         RegexReplacement::new("WHERE[ ]+AND[ ]+", "WHERE "),
         RegexReplacement::new("WHERE[ ]+OR[ ]+", "WHERE "),
         RegexReplacement::new(",[ ]+WHERE", " WHERE"),
         RegexReplacement::new("[ ]*,[ ]*\\)", ")"),
         RegexReplacement::new(",$", ""),
-    ];
+    ]
 }
 
 pub struct MyBatisParser {
     dialect_type: DialectType,
+    normalize_mode: NormalizeMode,
+    placeholder_style: PlaceholderStyle,
+    config: XBatisConfig,
     re_vec: Vec<RegexReplacement>,
 }
 
@@ -50,15 +57,31 @@ impl Parser for MyBatisParser {
         self.dialect_type = dialect_type;
     }
 
-    fn detect_match(&self, file: &String) -> bool {
-        return self.detect_match_with_regex(file, &RE);
+    fn dialect_type(&self) -> &DialectType {
+        &self.dialect_type
+    }
+
+    fn placeholder_style(&self) -> &PlaceholderStyle {
+        &self.placeholder_style
+    }
+
+    fn normalize_mode(&self) -> &NormalizeMode {
+        &self.normalize_mode
+    }
+
+    fn config(&self) -> &XBatisConfig {
+        &self.config
+    }
+
+    fn detect_content(&self, content: &str) -> bool {
+        RE.is_match(content)
     }
 
     fn ex_parse_start_element(
         &self,
         _name: OwnedName,
-        element_name: &String,
-        attributes: &Vec<OwnedAttribute>,
+        element_name: &str,
+        attributes: &[OwnedAttribute],
         state: &mut XmlParsedState,
     ) {
         if element_name == "set" {
@@ -88,18 +111,10 @@ impl Parser for MyBatisParser {
     fn ex_parse_end_element(
         &self,
         _name: OwnedName,
-        element_name: &String,
+        element_name: &str,
         state: &mut XmlParsedState,
     ) {
-        if element_name == "trim" {
-            let suffix;
-            {
-                suffix = &state.loop_def.suffix;
-            }
-            self.fill_content(state, suffix.clone());
-            state.in_loop = false;
-            state.loop_def.reset();
-        } else if element_name == "foreach" {
+        if element_name == "trim" || element_name == "foreach" {
             let suffix;
             {
                 suffix = &state.loop_def.suffix;
@@ -110,7 +125,15 @@ impl Parser for MyBatisParser {
         }
     }
 
-    fn clear_and_push(&self, sql_store: &mut Vec<String>, origin_sql: &String) {
-        self.loop_clear_and_push(sql_store, &self.re_vec, origin_sql)
+    fn collect_params(&self, origin_sql: &str) -> Vec<SqlParam> {
+        collect_params_with(&PARAM_RE, origin_sql)
+    }
+
+    fn collect_substitutions(&self, origin_sql: &str) -> Vec<String> {
+        collect_matched(&SUBSTITUTION_RE, origin_sql)
+    }
+
+    fn clear(&self, origin_sql: &str) -> String {
+        self.loop_clear(&self.re_vec, origin_sql)
     }
 }
@@ -1,16 +1,18 @@
+use super::def::*;
+use regex::Regex;
 use std::*;
 use xml::attribute::*;
 
 /// 替换 `include`，用对应的 `sql` 进行合并
-pub fn replace_included_sql(orig_sql: &String, id: &String, sql_part: &String) -> String {
-    let replace_target = "__INCLUDE_ID_".to_string() + &id.as_str() + "_END__";
-    let replaced = sql_part.as_str();
-    return orig_sql.replace(replace_target.as_str(), replaced);
+pub fn replace_included_sql(orig_sql: &str, id: &str, sql_part: &str) -> String {
+    let replace_target = "__INCLUDE_ID_".to_string() + id + "_END__";
+    let replaced = sql_part;
+    orig_sql.replace(replace_target.as_str(), replaced)
 }
 
 /// 检索属性，匹配情况下回调闭包
 pub fn search_matched_attr(
-    attributes: &Vec<OwnedAttribute>,
+    attributes: &[OwnedAttribute],
     matched_name: &str,
     mut f: impl FnMut(&OwnedAttribute),
 ) {
@@ -23,11 +25,111 @@ pub fn search_matched_attr(
 }
 
 /// 是否匹配语句块
-pub fn match_statement(element_name: &String) -> bool {
-    return *element_name == "statement"
-        || *element_name == "select"
-        || *element_name == "insert"
-        || *element_name == "update"
-        || *element_name == "delete"
-        || *element_name == "sql"
+pub fn match_statement(element_name: &str) -> bool {
+    element_name == "statement"
+        || element_name == "select"
+        || element_name == "insert"
+        || element_name == "update"
+        || element_name == "delete"
+        || element_name == "sql"
+}
+
+/// 按开始标签与结束标签的位置从文件内容中截取原始 `xml` 片段
+pub fn xml_fragment(content: &str, span: SourceSpan) -> String {
+    let start = byte_offset(content, span.start);
+    let end_tag = byte_offset(content, span.end);
+    match content[end_tag..].find('>') {
+        Some(i) if start <= end_tag => content[start..end_tag + i + 1].to_string(),
+        _ => String::from(""),
+    }
+}
+
+/// 行号、列号转为字节偏移，列号按字符计
+fn byte_offset(content: &str, pos: SourcePos) -> usize {
+    let mut offset = 0;
+    for (i, line) in content.split_inclusive('\n').enumerate() {
+        if i + 1 == pos.line as usize {
+            let column = pos.column.saturating_sub(1) as usize;
+            return offset
+                + line
+                    .char_indices()
+                    .nth(column)
+                    .map_or(line.len(), |(j, _)| j);
+        }
+        offset += line.len();
+    }
+    content.len()
+}
+
+/// 解析参数文本，支持 `#{prop,jdbcType=VARCHAR,mode=OUT}` / `#prop,jdbcType=VARCHAR#` / `#prop:VARCHAR#`
+pub fn parse_param(origin: &str) -> SqlParam {
+    let mut param = SqlParam {
+        text: origin.to_string(),
+        property: param_property(origin),
+        jdbc_type: String::from(""),
+        java_type: String::from(""),
+        mode: String::from(""),
+        type_handler: String::from(""),
+        numeric_scale: String::from(""),
+    };
+    let inner = origin
+        .trim_start_matches(['#', '$'])
+        .trim_start_matches('{')
+        .trim_end_matches(['#', '$', '}']);
+    if inner.contains('=') {
+        for pair in inner.split(',').skip(1) {
+            if let Some((key, value)) = pair.split_once('=') {
+                let value = value.trim().to_string();
+                match key.trim() {
+                    "jdbcType" => param.jdbc_type = value,
+                    "javaType" => param.java_type = value,
+                    "mode" => param.mode = value,
+                    "typeHandler" => param.type_handler = value,
+                    "numericScale" => param.numeric_scale = value,
+                    _ => {}
+                }
+            }
+        }
+    } else if let Some(jdbc_type) = inner.split(':').nth(1) {
+        param.jdbc_type = jdbc_type.trim().to_string();
+    }
+    param
+}
+
+/// 按出现顺序收集匹配的文本
+pub fn collect_matched(re: &Regex, sql: &str) -> Vec<String> {
+    re.find_iter(sql).map(|m| m.as_str().to_string()).collect()
+}
+
+/// 按出现顺序收集并解析参数
+pub fn collect_params_with(re: &Regex, sql: &str) -> Vec<SqlParam> {
+    re.find_iter(sql).map(|m| parse_param(m.as_str())).collect()
+}
+
+/// 取参数的属性名，如 `#{user.id,jdbcType=VARCHAR}` / `#user.id:VARCHAR#` 均为 `user.id`
+pub fn param_property(origin: &str) -> String {
+    let inner = origin
+        .trim_start_matches(['#', '$'])
+        .trim_start_matches('{')
+        .trim_end_matches(['#', '$', '}']);
+    match inner.find([',', ':']) {
+        Some(i) => inner[..i].trim().to_string(),
+        None => inner.trim().to_string(),
+    }
+}
+
+/// 按样式生成第 `index` 个（从 1 开始计数）参数的占位符
+pub fn render_placeholder(style: &PlaceholderStyle, index: usize, origin: &str) -> String {
+    match style {
+        PlaceholderStyle::Fixed(text) => text.to_string(),
+        PlaceholderStyle::Indexed(prefix) => format!("{}{}", prefix, index),
+        PlaceholderStyle::Named => {
+            let name: String = param_property(origin)
+                .chars()
+                .map(|c| if c.is_alphanumeric() { c } else { '_' })
+                .collect();
+            format!(":{}", name.trim_matches('_'))
+        }
+        PlaceholderStyle::Keep => origin.to_string(),
+    }
 }
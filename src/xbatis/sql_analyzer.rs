@@ -0,0 +1,797 @@
+use super::def::*;
+use super::sql_tokenizer::*;
+use std::collections::{HashMap, HashSet};
+use std::*;
+
+/// 形似列名但不是列的单词：伪列、时间单位、窗口范围等
+const NON_COLUMN_WORDS: &[&str] = &[
+    "CURRENT_DATE",
+    "CURRENT_TIME",
+    "CURRENT_TIMESTAMP",
+    "LOCALTIME",
+    "LOCALTIMESTAMP",
+    "SYSDATE",
+    "SYSTIMESTAMP",
+    "LEVEL",
+    "ROWID",
+    "DUAL",
+    "DATE",
+    "TIME",
+    "TIMESTAMP",
+    "YEAR",
+    "MONTH",
+    "DAY",
+    "HOUR",
+    "MINUTE",
+    "SECOND",
+    "ROWNUM",
+    "UNBOUNDED",
+    "PRECEDING",
+    "FOLLOWING",
+    "__REPLACE_SCHEMA__",
+];
+
+/// 无法合并的 `<include>` 保留的标记
+const INCLUDE_MARK: &str = "__INCLUDE_ID_";
+
+/// 用作列名时仍按关键字处理的单词
+const VALUE_KEYWORDS: &[&str] = &["NULL", "TRUE", "FALSE", "DEFAULT"];
+
+/// 简化后的词法单元，空白与注释已去掉，未加引号的名称转为大写，加引号的保持原样
+#[derive(Clone, PartialEq)]
+enum Tok {
+    /// 关键字
+    Keyword(String),
+    /// 标识符，包括加引号的
+    Name(String),
+    /// 字符串、数字、参数及占位符
+    Value,
+    Symbol(char),
+}
+
+/// 语句中出现的列，`explicit` 为 `INSERT` 列清单、`SET` 赋值等明确所属的表
+struct ColumnRef {
+    qualifier: Option<String>,
+    column: String,
+    explicit: Option<String>,
+    /// 所在的括号范围
+    scope: usize,
+}
+
+/// 一对括号内的范围，根范围为整个语句
+struct Scope {
+    parent: Option<usize>,
+    /// 其中 `FROM` / `JOIN` 等引用的表，`INSERT` 的目标表除外
+    tables: Vec<String>,
+    /// 其中有子查询、`WITH` 定义的名称或表函数
+    derived: bool,
+}
+
+/// 分析整理后的语句，收集涉及的表（访问方式、别名）及列；容忍各方言的占位符，无法识别的部分跳过。
+/// 返回涉及的表及无法确定所属表的列
+pub fn analyze_tables(sql: &str) -> (Vec<TableRef>, Vec<String>) {
+    let mut analyzer = Analyzer {
+        toks: simplify(sql),
+        refs: Vec::new(),
+        aliases: HashMap::new(),
+        ctes: HashSet::new(),
+        columns: Vec::new(),
+        dml: None,
+        target: None,
+        delete_targets: Vec::new(),
+        set_depth: None,
+        depth: 0,
+        scopes: vec![Scope {
+            parent: None,
+            tables: Vec::new(),
+            derived: false,
+        }],
+        scope: 0,
+    };
+    analyzer.collect_ctes();
+    analyzer.run();
+    analyzer.resolve_delete_targets();
+    analyzer.resolve()
+}
+
+/// 最外层 `SELECT` 的列标签：别名或列名，无别名的表达式及 `*` 跳过
+pub fn select_labels(sql: &str) -> Vec<String> {
+    let toks = simplify(sql);
+    let mut depth = 0;
+    let mut start = None;
+    for (i, tok) in toks.iter().enumerate() {
+        match tok {
+            Tok::Symbol('(') => depth += 1,
+            Tok::Symbol(')') => depth -= 1,
+            Tok::Keyword(k) if depth == 0 && k == "SELECT" => {
+                start = Some(i + 1);
+                break;
+            }
+            _ => {}
+        }
+    }
+    let mut labels: Vec<String> = Vec::new();
+    let Some(start) = start else {
+        return labels;
+    };
+    let mut item: Vec<&Tok> = Vec::new();
+    for tok in toks[start..].iter() {
+        match tok {
+            Tok::Symbol('(') => depth += 1,
+            Tok::Symbol(')') => depth -= 1,
+            _ => {}
+        }
+        if depth == 0 {
+            match tok {
+                Tok::Keyword(k) if k == "FROM" || k == "INTO" => break,
+                Tok::Keyword(k) if item.is_empty() && (k == "DISTINCT" || k == "ALL") => {
+                    continue;
+                }
+                Tok::Symbol(',') => {
+                    labels.extend(item_label(&item));
+                    item.clear();
+                    continue;
+                }
+                _ => {}
+            }
+        }
+        item.push(tok);
+    }
+    labels.extend(item_label(&item));
+    labels
+}
+
+/// 选择项的标签：`col` / `t.col` / `expr AS alias` / `expr alias`
+fn item_label(item: &[&Tok]) -> Option<String> {
+    let Some(Tok::Name(name)) = item.last() else {
+        return None;
+    };
+    match item.len() {
+        1 => Some(name.clone()),
+        len => match item[len - 2] {
+            Tok::Symbol('.') => Some(name.clone()),
+            prev if is_expression_end(prev) => Some(name.clone()),
+            _ => None,
+        },
+    }
+}
+
+struct Analyzer {
+    toks: Vec<Tok>,
+    refs: Vec<TableRef>,
+    /// 别名到表名
+    aliases: HashMap<String, String>,
+    /// `WITH` 定义的名称，不作为表
+    ctes: HashSet<String>,
+    columns: Vec<ColumnRef>,
+    /// 最近的 `INSERT` / `UPDATE` / `DELETE` / `MERGE`
+    dml: Option<TableAccess>,
+    /// 写入的目标表
+    target: Option<String>,
+    /// 多表删除中删除的表，可能为别名
+    delete_targets: Vec<String>,
+    /// `SET` 赋值所在的括号层级
+    set_depth: Option<i32>,
+    depth: i32,
+    scopes: Vec<Scope>,
+    /// 当前所在的范围
+    scope: usize,
+}
+
+impl Analyzer {
+    fn keyword_at(&self, i: usize, keyword: &str) -> bool {
+        matches!(self.toks.get(i), Some(Tok::Keyword(k)) if k == keyword)
+    }
+
+    fn symbol_at(&self, i: usize, c: char) -> bool {
+        self.toks.get(i) == Some(&Tok::Symbol(c))
+    }
+
+    fn name_at(&self, i: usize) -> Option<String> {
+        match self.toks.get(i) {
+            Some(Tok::Name(name)) => Some(name.clone()),
+            _ => None,
+        }
+    }
+
+    /// `WITH x AS (` / `, y AS (` 中的名称
+    fn collect_ctes(&mut self) {
+        for i in 2..self.toks.len() {
+            if self.keyword_at(i, "AS") && self.symbol_at(i + 1, '(') {
+                if let Some(name) = self.name_at(i - 1) {
+                    if self.keyword_at(i - 2, "WITH")
+                        || self.keyword_at(i - 2, "RECURSIVE")
+                        || self.symbol_at(i - 2, ',')
+                    {
+                        self.ctes.insert(name);
+                    }
+                }
+            }
+        }
+    }
+
+    fn run(&mut self) {
+        let mut i = 0;
+        while i < self.toks.len() {
+            i = match self.toks[i].clone() {
+                Tok::Symbol('(') => {
+                    self.depth += 1;
+                    self.scopes.push(Scope {
+                        parent: Some(self.scope),
+                        tables: Vec::new(),
+                        derived: false,
+                    });
+                    self.scope = self.scopes.len() - 1;
+                    i + 1
+                }
+                Tok::Symbol(')') => {
+                    self.depth -= 1;
+                    self.scope = self.scopes[self.scope].parent.unwrap_or(0);
+                    if self.set_depth.is_some_and(|d| self.depth < d) {
+                        self.set_depth = None;
+                    }
+                    i + 1
+                }
+                Tok::Keyword(keyword) => self.on_keyword(i, &keyword),
+                Tok::Name(_) => self.on_name(i),
+                Tok::Symbol('*') if self.is_select_all(i) => {
+                    self.push_column(None, String::from("*"), None);
+                    i + 1
+                }
+                _ => i + 1,
+            };
+        }
+    }
+
+    fn on_keyword(&mut self, i: usize, keyword: &str) -> usize {
+        if self.set_depth == Some(self.depth)
+            && ["WHERE", "FROM", "WHEN", "RETURNING"].contains(&keyword)
+        {
+            self.set_depth = None;
+        }
+        match keyword {
+            "FROM" => {
+                let access = if i > 0 && self.keyword_at(i - 1, "DELETE") {
+                    TableAccess::Delete
+                } else {
+                    TableAccess::Select
+                };
+                return self.table_list(i + 1, access);
+            }
+            "JOIN" => return self.table(i + 1, TableAccess::Select).1,
+            "USING" if matches!(self.dml, Some(TableAccess::Merge | TableAccess::Delete)) => {
+                return self.table_list(i + 1, TableAccess::Select);
+            }
+            // `MERGE` 中的 `INSERT (...)`
+            "INSERT" if self.symbol_at(i + 1, '(') => return self.column_list(i + 1),
+            "INSERT" => self.dml = Some(TableAccess::Insert),
+            "REPLACE" if self.keyword_at(i + 1, "INTO") => self.dml = Some(TableAccess::Insert),
+            "MERGE" => self.dml = Some(TableAccess::Merge),
+            "INTO" => {
+                if let Some(access) = self
+                    .dml
+                    .filter(|a| matches!(a, TableAccess::Insert | TableAccess::Merge))
+                {
+                    let (table, next) = self.table(i + 1, access);
+                    if table.is_some() {
+                        self.target = table;
+                    }
+                    if access == TableAccess::Insert && self.symbol_at(next, '(') {
+                        return self.column_list(next);
+                    }
+                    return next;
+                }
+            }
+            "UPDATE" => {
+                if self.keyword_at(i + 1, "SET") {
+                    // `MERGE` 的 `UPDATE SET`、`ON CONFLICT DO UPDATE SET`
+                } else if i > 0 && self.keyword_at(i - 1, "KEY") {
+                    // `ON DUPLICATE KEY UPDATE`
+                    self.set_depth = Some(self.depth);
+                } else if i == 0 || !self.keyword_at(i - 1, "FOR") {
+                    self.dml = Some(TableAccess::Update);
+                    let (table, next) = self.table(i + 1, TableAccess::Update);
+                    if table.is_some() {
+                        self.target = table;
+                    }
+                    return next;
+                }
+            }
+            "SET" => self.set_depth = Some(self.depth),
+            "DELETE" => {
+                self.dml = Some(TableAccess::Delete);
+                if let Some(next) = self.delete_targets(i + 1) {
+                    return next;
+                }
+                if self.name_at(i + 1).is_some() {
+                    return self.table(i + 1, TableAccess::Delete).1;
+                }
+            }
+            _ => {}
+        }
+        i + 1
+    }
+
+    /// 可能的列名；`x.y` 形式时前缀为表或别名
+    fn on_name(&mut self, i: usize) -> usize {
+        let mut parts: Vec<String> = vec![self.name_at(i).unwrap()];
+        let mut next = i + 1;
+        while self.symbol_at(next, '.') {
+            match self.toks.get(next + 1) {
+                Some(Tok::Name(name)) => parts.push(name.clone()),
+                Some(Tok::Symbol('*')) => parts.push(String::from("*")),
+                // 无法识别的部分，不把限定名当作列
+                _ => return next + 1,
+            }
+            next += 2;
+        }
+        // 函数
+        if self.symbol_at(next, '(') {
+            return next;
+        }
+        // 别名：紧跟在表达式之后，或在 `AS` 之后
+        if i > 0 && is_expression_end(&self.toks[i - 1]) {
+            return next;
+        }
+        let column = parts.pop().unwrap();
+        if parts.is_empty()
+            && (NON_COLUMN_WORDS.contains(&column.as_str())
+                || self.ctes.contains(&column)
+                || column.starts_with(INCLUDE_MARK))
+        {
+            return next;
+        }
+        // `SET` 赋值的左侧；`ON DUPLICATE KEY UPDATE` 中的 `VALUES(col)`
+        let explicit = if parts.is_empty()
+            && ((self.set_depth == Some(self.depth) && self.symbol_at(next, '='))
+                || (i > 1
+                    && self.dml == Some(TableAccess::Insert)
+                    && self.keyword_at(i - 2, "VALUES")
+                    && self.symbol_at(i - 1, '(')
+                    && self.symbol_at(next, ')')))
+        {
+            self.target.clone()
+        } else {
+            None
+        };
+        self.push_column(parts.pop(), column, explicit);
+        next
+    }
+
+    /// `SELECT *` / `SELECT a, *`
+    fn is_select_all(&self, i: usize) -> bool {
+        i > 0
+            && (self.keyword_at(i - 1, "SELECT")
+                || self.keyword_at(i - 1, "DISTINCT")
+                || self.symbol_at(i - 1, ','))
+            && (self.keyword_at(i + 1, "FROM") || self.symbol_at(i + 1, ','))
+    }
+
+    fn push_column(&mut self, qualifier: Option<String>, column: String, explicit: Option<String>) {
+        self.columns.push(ColumnRef {
+            qualifier,
+            column,
+            explicit,
+            scope: self.scope,
+        });
+    }
+
+    /// `MySQL` 的多表删除 `DELETE a, b FROM orders a JOIN ...`，删除的表以别名或表名给出，
+    /// 读完 `FROM` 后再确定；不是这种写法时返回 `None`
+    fn delete_targets(&mut self, start: usize) -> Option<usize> {
+        let mut targets: Vec<String> = Vec::new();
+        let mut next = start;
+        loop {
+            let mut name = self.name_at(next)?;
+            next += 1;
+            while self.symbol_at(next, '.') {
+                match self.name_at(next + 1) {
+                    Some(part) => name = name + "." + &part,
+                    // `t.*`
+                    None if self.symbol_at(next + 1, '*') => {}
+                    None => return None,
+                }
+                next += 2;
+            }
+            targets.push(name);
+            if !self.symbol_at(next, ',') {
+                break;
+            }
+            next += 1;
+        }
+        if !self.keyword_at(next, "FROM") {
+            return None;
+        }
+        self.delete_targets.extend(targets);
+        Some(next)
+    }
+
+    /// `FROM a x, b y`
+    fn table_list(&mut self, start: usize, access: TableAccess) -> usize {
+        let (_, mut next) = self.table(start, access);
+        while self.symbol_at(next, ',') && self.name_at(next + 1).is_some() {
+            next = self.table(next + 1, access).1;
+        }
+        next
+    }
+
+    /// 读取表名及别名，返回表名及其后的位置；子查询、表函数等返回 `None`
+    fn table(&mut self, start: usize, access: TableAccess) -> (Option<String>, usize) {
+        let mut name = match self.name_at(start) {
+            Some(name) => name,
+            None => {
+                // 子查询
+                self.scopes[self.scope].derived = true;
+                return (None, start);
+            }
+        };
+        let mut next = start + 1;
+        while self.symbol_at(next, '.') {
+            match self.name_at(next + 1) {
+                Some(part) => name = name + "." + &part,
+                None => break,
+            }
+            next += 2;
+        }
+        // 表函数；`INSERT INTO t (...)` 中为列清单
+        if access == TableAccess::Select && self.symbol_at(next, '(') {
+            self.scopes[self.scope].derived = true;
+            return (None, start);
+        }
+        let alias = if self.keyword_at(next, "AS") {
+            next += 1;
+            self.name_at(next)
+        } else {
+            self.name_at(next)
+        };
+        if alias.is_some() {
+            next += 1;
+        }
+        if name == "DUAL" {
+            return (None, next);
+        }
+        if self.ctes.contains(&name) || name.starts_with(INCLUDE_MARK) {
+            self.scopes[self.scope].derived = true;
+            return (None, next);
+        }
+        self.add_ref(&name, access, alias);
+        let tables = &mut self.scopes[self.scope].tables;
+        if access != TableAccess::Insert && !tables.contains(&name) {
+            tables.push(name.clone());
+        }
+        (Some(name), next)
+    }
+
+    /// `(a, b, c)`，均属于写入的目标表
+    fn column_list(&mut self, start: usize) -> usize {
+        let mut next = start + 1;
+        while next < self.toks.len() && !self.symbol_at(next, ')') {
+            if let Some(column) = self.name_at(next) {
+                self.push_column(None, column, self.target.clone());
+            }
+            next += 1;
+        }
+        next + 1
+    }
+
+    fn add_ref(&mut self, table: &String, access: TableAccess, alias: Option<String>) {
+        if !self.refs.iter().any(|r| &r.table == table) {
+            self.refs.push(TableRef {
+                table: table.clone(),
+                accesses: Vec::new(),
+                aliases: Vec::new(),
+                columns: Vec::new(),
+            });
+        }
+        let table_ref = self.refs.iter_mut().find(|r| &r.table == table).unwrap();
+        if !table_ref.accesses.contains(&access) {
+            table_ref.accesses.push(access);
+        }
+        if let Some(alias) = alias {
+            if !table_ref.aliases.contains(&alias) {
+                table_ref.aliases.push(alias.clone());
+            }
+            self.aliases.insert(alias, table.clone());
+        }
+    }
+
+    /// 按别名、表名确定列所属的表；
+    /// 未限定的列由内向外查找最近的引用了表的范围，其中只有一个表时归于该表
+    fn resolve(mut self) -> (Vec<TableRef>, Vec<String>) {
+        let mut unresolved: Vec<String> = Vec::new();
+        let columns = mem::take(&mut self.columns);
+        for column_ref in columns {
+            let table = match (&column_ref.explicit, &column_ref.qualifier) {
+                (Some(table), _) => Some(table.clone()),
+                (None, Some(qualifier)) => self.find_table(qualifier),
+                (None, None) => match self.scope_table(column_ref.scope) {
+                    Ok(table) => table,
+                    Err(_) => {
+                        if !unresolved.contains(&column_ref.column) {
+                            unresolved.push(column_ref.column.clone());
+                        }
+                        None
+                    }
+                },
+            };
+            if let Some(table_ref) = table.and_then(|t| self.refs.iter_mut().find(|r| r.table == t))
+            {
+                if !table_ref.columns.contains(&column_ref.column) {
+                    table_ref.columns.push(column_ref.column);
+                }
+            }
+        }
+        (self.refs, unresolved)
+    }
+
+    /// 多表删除的目标按别名或表名归于 `FROM` 中的表
+    fn resolve_delete_targets(&mut self) {
+        for target in mem::take(&mut self.delete_targets) {
+            let table = self.find_table(&target).unwrap_or(target);
+            self.add_ref(&table, TableAccess::Delete, None);
+            let table_ref = self.refs.iter_mut().find(|r| r.table == table).unwrap();
+            // 按出现顺序，`DELETE` 在 `FROM` 中的访问之前
+            table_ref.accesses.retain(|a| *a != TableAccess::Delete);
+            table_ref.accesses.insert(0, TableAccess::Delete);
+        }
+    }
+
+    /// 未限定的列所属的表；只来自子查询等时为 `None`，无法确定时为 `Err`
+    fn scope_table(&self, mut scope: usize) -> Result<Option<String>, ()> {
+        loop {
+            let current = &self.scopes[scope];
+            match (current.tables.len(), current.derived) {
+                (0, true) => return Ok(None),
+                (1, false) => return Ok(Some(current.tables[0].clone())),
+                (0, false) => {}
+                _ => return Err(()),
+            }
+            scope = match current.parent {
+                Some(parent) => parent,
+                // 如 `INSERT ... VALUES` 中只有目标表
+                None if self.refs.len() == 1 => return Ok(Some(self.refs[0].table.clone())),
+                None if self.refs.is_empty() => return Ok(None),
+                None => return Err(()),
+            };
+        }
+    }
+
+    /// 别名、完整表名或不含模式名的表名；子查询的别名等返回 `None`
+    fn find_table(&self, qualifier: &String) -> Option<String> {
+        if let Some(table) = self.aliases.get(qualifier) {
+            return Some(table.clone());
+        }
+        self.refs
+            .iter()
+            .find(|r| &r.table == qualifier || r.table.rsplit('.').next() == Some(qualifier))
+            .map(|r| r.table.clone())
+    }
+}
+
+/// 其后的名称为别名
+fn is_expression_end(tok: &Tok) -> bool {
+    match tok {
+        Tok::Name(_) | Tok::Value => true,
+        Tok::Symbol(c) => *c == ')' || *c == ']',
+        Tok::Keyword(k) => k == "AS" || k == "END",
+    }
+}
+
+/// 去掉空白与注释，合并 `[name]` 及各种占位符（`?` / `:?` / `:name` / `@1` / `@p1` / `$1`）
+fn simplify(sql: &str) -> Vec<Tok> {
+    let tokens: Vec<Token> = tokenize(sql)
+        .into_iter()
+        .filter(|t| t.kind != TokenKind::Whitespace && t.kind != TokenKind::Comment)
+        .collect();
+    let mut toks: Vec<Tok> = Vec::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        let token = &tokens[i];
+        let next = tokens.get(i + 1);
+        match token.kind {
+            TokenKind::Word
+                if is_keyword(&token.text) && !is_column_keyword(&toks, token, next) =>
+            {
+                toks.push(Tok::Keyword(token.text.to_ascii_uppercase()));
+            }
+            TokenKind::Word => toks.push(Tok::Name(token.text.to_ascii_uppercase())),
+            TokenKind::QuotedIdent => {
+                let name = token.text.trim_matches(['"', '`']);
+                toks.push(Tok::Name(name.to_string()));
+            }
+            TokenKind::Literal | TokenKind::Param => toks.push(Tok::Value),
+            TokenKind::Symbol => {
+                let c = token.text.chars().next().unwrap_or(' ');
+                if c.is_ascii_digit() || c == '?' {
+                    toks.push(Tok::Value);
+                } else if (c == ':' || c == '@' || c == '$')
+                    && next.is_some_and(|n| is_placeholder_rest(c, n))
+                {
+                    // 占位符，连同其后的名称或编号
+                    toks.push(Tok::Value);
+                    i += 1;
+                } else if c == '['
+                    && tokens.get(i + 2).is_some_and(|t| t.text == "]")
+                    && next.is_some_and(|n| n.kind == TokenKind::Word)
+                {
+                    toks.push(Tok::Name(next.unwrap().text.clone()));
+                    i += 2;
+                } else {
+                    toks.push(Tok::Symbol(c));
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    toks
+}
+
+/// 用作列名的关键字，如 `c.key`、`WHERE key = ?`、`SELECT start, ...`
+fn is_column_keyword(toks: &[Tok], token: &Token, next: Option<&Token>) -> bool {
+    let prev = toks.last();
+    if prev == Some(&Tok::Symbol('.')) {
+        return true;
+    }
+    if VALUE_KEYWORDS.contains(&token.text.to_ascii_uppercase().as_str()) {
+        return false;
+    }
+    let column_start = match prev {
+        Some(Tok::Symbol(c)) => [',', '(', '=', '<', '>'].contains(c),
+        Some(Tok::Keyword(k)) => [
+            "SELECT", "DISTINCT", "SET", "WHERE", "AND", "OR", "ON", "BY", "WHEN", "THEN", "ELSE",
+        ]
+        .contains(&k.as_str()),
+        _ => false,
+    };
+    let column_end = next.is_some_and(|n| {
+        ["=", ",", ")", "<", ">", "!"].contains(&n.text.as_str())
+            || n.text.eq_ignore_ascii_case("FROM")
+    });
+    column_start && column_end
+}
+
+/// 占位符前缀 `c` 之后的部分：`:?` / `:name` / `:1` / `@1` / `@p1` / `$1`
+fn is_placeholder_rest(c: char, next: &Token) -> bool {
+    let starts_with_digit = next.text.starts_with(|x: char| x.is_ascii_digit());
+    match c {
+        ':' => next.kind == TokenKind::Word || next.text == "?" || starts_with_digit,
+        '@' => next.kind == TokenKind::Word || starts_with_digit,
+        _ => next.kind == TokenKind::Symbol && starts_with_digit,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Summary = (String, Vec<String>, Vec<String>, Vec<String>);
+
+    /// 涉及的表：表名、访问方式、别名及列
+    fn tables(sql: &str) -> Vec<Summary> {
+        let (refs, unresolved) = analyze_tables(sql);
+        assert!(unresolved.is_empty(), "unresolved columns {:?}", unresolved);
+        refs.into_iter()
+            .map(|r| {
+                let accesses = r.accesses.iter().map(|a| a.name().to_string()).collect();
+                (r.table, accesses, r.aliases, r.columns)
+            })
+            .collect()
+    }
+
+    fn table(name: &str, accesses: &[&str], aliases: &[&str], columns: &[&str]) -> Summary {
+        let strings = |items: &[&str]| items.iter().map(|s| s.to_string()).collect();
+        (
+            name.to_string(),
+            strings(accesses),
+            strings(aliases),
+            strings(columns),
+        )
+    }
+
+    #[test]
+    fn select_with_join() {
+        assert_eq!(
+            tables(
+                "SELECT o.id, u.name FROM orders o JOIN users u ON o.user_id = u.id \
+                 WHERE o.status = ?"
+            ),
+            vec![
+                table("ORDERS", &["SELECT"], &["O"], &["ID", "USER_ID", "STATUS"]),
+                table("USERS", &["SELECT"], &["U"], &["NAME", "ID"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn cte_is_not_a_table() {
+        assert_eq!(
+            tables(
+                "WITH recent AS (SELECT id, user_id FROM orders WHERE created > ?) \
+                 SELECT r.id, u.name FROM recent r JOIN users u ON r.user_id = u.id"
+            ),
+            vec![
+                table("ORDERS", &["SELECT"], &[], &["ID", "USER_ID", "CREATED"]),
+                table("USERS", &["SELECT"], &["U"], &["NAME", "ID"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn merge() {
+        assert_eq!(
+            tables(
+                "MERGE INTO accounts a USING staging s ON (a.id = s.id) \
+                 WHEN MATCHED THEN UPDATE SET a.balance = s.balance \
+                 WHEN NOT MATCHED THEN INSERT (id, balance) VALUES (s.id, s.balance)"
+            ),
+            vec![
+                table("ACCOUNTS", &["MERGE"], &["A"], &["ID", "BALANCE"]),
+                table("STAGING", &["SELECT"], &["S"], &["ID", "BALANCE"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn multi_table_update() {
+        assert_eq!(
+            tables(
+                "UPDATE orders o JOIN users u ON o.user_id = u.id \
+                 SET o.status = ? WHERE u.level = ?"
+            ),
+            vec![
+                table("ORDERS", &["UPDATE"], &["O"], &["USER_ID", "STATUS"]),
+                table("USERS", &["SELECT"], &["U"], &["ID", "LEVEL"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn multi_table_delete() {
+        assert_eq!(
+            tables("DELETE o FROM orders o JOIN users u ON o.user_id = u.id WHERE u.state = ?"),
+            vec![
+                table("ORDERS", &["DELETE", "SELECT"], &["O"], &["USER_ID"]),
+                table("USERS", &["SELECT"], &["U"], &["ID", "STATE"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn keyword_as_column() {
+        assert_eq!(
+            tables("SELECT c.key, c.value FROM config c WHERE key = ?"),
+            vec![table("CONFIG", &["SELECT"], &["C"], &["KEY", "VALUE"])]
+        );
+    }
+
+    #[test]
+    fn quoted_identifiers_keep_case() {
+        assert_eq!(
+            tables(
+                "SELECT \"Order\".\"Id\", `order`.name, [Order].x \
+                 FROM \"Order\" JOIN `order` ON 1 = 1 JOIN [Order] ON 1 = 1"
+            ),
+            vec![
+                table("Order", &["SELECT"], &[], &["Id", "X"]),
+                table("order", &["SELECT"], &[], &["NAME"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn placeholders() {
+        for placeholder in ["?", ":1", ":id", "$1", "@p1", "/*${x}*/ 0"] {
+            let sql = format!(
+                "SELECT name FROM users WHERE id = {} AND state = 1",
+                placeholder
+            );
+            assert_eq!(
+                tables(&sql),
+                vec![table("USERS", &["SELECT"], &[], &["NAME", "ID", "STATE"])],
+                "{}",
+                sql
+            );
+        }
+    }
+}
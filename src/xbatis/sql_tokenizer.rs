@@ -0,0 +1,401 @@
+use std::*;
+
+/// 改写时标记字符串的首尾，取自私用区
+const LITERAL_MARK_START: char = '\u{E000}';
+const LITERAL_MARK_END: char = '\u{E001}';
+/// 改写时标记占位符及文本替换的首尾，同取自私用区
+const SPAN_MARK_START: char = '\u{E002}';
+const SPAN_MARK_END: char = '\u{E003}';
+
+/// 关键字，仅这些单词会被转为大写
+const KEYWORDS: &[&str] = &[
+    "ADD",
+    "ALL",
+    "ALTER",
+    "AND",
+    "ANY",
+    "AS",
+    "ASC",
+    "BEGIN",
+    "BETWEEN",
+    "BY",
+    "CALL",
+    "CASCADE",
+    "CASE",
+    "CAST",
+    "CHECK",
+    "COLUMN",
+    "COMMIT",
+    "CONFLICT",
+    "CONNECT",
+    "CONSTRAINT",
+    "CREATE",
+    "CROSS",
+    "CURRENT",
+    "DECLARE",
+    "DEFAULT",
+    "DELETE",
+    "DESC",
+    "DISTINCT",
+    "DO",
+    "DROP",
+    "DUPLICATE",
+    "ELSE",
+    "END",
+    "ESCAPE",
+    "EXCEPT",
+    "EXEC",
+    "EXECUTE",
+    "EXISTS",
+    "FALSE",
+    "FETCH",
+    "FIRST",
+    "FOR",
+    "FOREIGN",
+    "FROM",
+    "FULL",
+    "GRANT",
+    "GROUP",
+    "HAVING",
+    "IGNORE",
+    "IN",
+    "INDEX",
+    "INNER",
+    "INSERT",
+    "INTERSECT",
+    "INTERVAL",
+    "INTO",
+    "IS",
+    "JOIN",
+    "KEY",
+    "LAST",
+    "LATERAL",
+    "LEFT",
+    "LIKE",
+    "LIMIT",
+    "LOCK",
+    "MATCHED",
+    "MERGE",
+    "MINUS",
+    "NATURAL",
+    "NEXT",
+    "NOCYCLE",
+    "NOT",
+    "NOTHING",
+    "NOWAIT",
+    "NULL",
+    "NULLS",
+    "OF",
+    "OFFSET",
+    "ON",
+    "ONLY",
+    "OR",
+    "ORDER",
+    "OUTER",
+    "OVER",
+    "PARTITION",
+    "PERCENT",
+    "PIVOT",
+    "PRIMARY",
+    "PRIOR",
+    "PROCEDURE",
+    "RECURSIVE",
+    "REFERENCES",
+    "REPLACE",
+    "RETURNING",
+    "RIGHT",
+    "ROLLBACK",
+    "ROW",
+    "ROWNUM",
+    "ROWS",
+    "SELECT",
+    "SET",
+    "SHARE",
+    "SIBLINGS",
+    "SKIP",
+    "SOME",
+    "START",
+    "STRAIGHT_JOIN",
+    "TABLE",
+    "THEN",
+    "TIES",
+    "TO",
+    "TOP",
+    "TRUE",
+    "TRUNCATE",
+    "UNION",
+    "UNIQUE",
+    "UPDATE",
+    "UPSERT",
+    "USING",
+    "VALUES",
+    "VIEW",
+    "WAIT",
+    "WHEN",
+    "WHERE",
+    "WINDOW",
+    "WITH",
+    "WITHIN",
+];
+
+/// 词法单元类型
+#[derive(Clone, Copy, PartialEq)]
+pub enum TokenKind {
+    /// 单词：关键字或未加引号的标识符
+    Word,
+    /// 字符串字面量，`'...'`
+    Literal,
+    /// 加引号的标识符，`"..."` 或 `` `...` ``
+    QuotedIdent,
+    /// 注释，`-- ...` 或 `/* ... */`
+    Comment,
+    /// 参数，`#{...}` / `${...}` / `#...#` / `$...$`
+    Param,
+    /// 空白
+    Whitespace,
+    /// 数字、运算符、标点等
+    Symbol,
+}
+
+/// 词法单元
+pub struct Token {
+    pub kind: TokenKind,
+    pub text: String,
+}
+
+/// 切分语句，所有词法单元首尾相接即为原语句
+pub fn tokenize(sql: &str) -> Vec<Token> {
+    let chars: Vec<char> = sql.chars().collect();
+    let mut tokens: Vec<Token> = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let (kind, end) = next_token(&chars, i);
+        tokens.push(Token {
+            kind,
+            text: chars[i..end].iter().collect(),
+        });
+        i = end;
+    }
+    tokens
+}
+
+/// 仅将关键字转为大写，字符串、加引号的标识符、注释及参数保持原样
+pub fn uppercase_keywords(sql: &str) -> String {
+    let mut result = String::with_capacity(sql.len());
+    for token in tokenize(sql) {
+        if token.kind == TokenKind::Word && is_keyword(&token.text) {
+            result += token.text.to_ascii_uppercase().as_str();
+        } else {
+            result += token.text.as_str();
+        }
+    }
+    result
+}
+
+/// 将 `` `...` `` 包围的标识符改用 `quote` 加引号，`"..."` 包围的标识符保持原样
+pub fn requote_identifiers(sql: &str, quote: impl Fn(&str) -> String) -> String {
+    let mut result = String::with_capacity(sql.len());
+    for token in tokenize(sql) {
+        if token.kind == TokenKind::QuotedIdent
+            && token.text.len() > 1
+            && token.text.starts_with('`')
+        {
+            result += quote(&token.text[1..token.text.len() - 1]).as_str();
+        } else {
+            result += token.text.as_str();
+        }
+    }
+    result
+}
+
+/// 去掉 `--` 注释并将连续的空白合并为一个空格，字符串及加引号的标识符保持原样
+pub fn collapse_whitespace(sql: &str) -> String {
+    let mut result = String::with_capacity(sql.len());
+    for token in tokenize(sql) {
+        match token.kind {
+            TokenKind::Literal | TokenKind::QuotedIdent => result += token.text.as_str(),
+            TokenKind::Comment if token.text.starts_with("--") => push_space(&mut result),
+            _ => {
+                for c in token.text.chars() {
+                    if c.is_whitespace() {
+                        push_space(&mut result);
+                    } else {
+                        result.push(c);
+                    }
+                }
+            }
+        }
+    }
+    result
+}
+
+fn push_space(result: &mut String) {
+    if !result.ends_with(' ') {
+        result.push(' ');
+    }
+}
+
+/// 字符串及加引号的标识符替换为标记后交给 `f` 改写，再还原，`f` 的改写不会触及其中的内容
+pub fn rewrite_outside_literals(sql: &str, f: impl FnOnce(String) -> String) -> String {
+    let mut literals: Vec<String> = Vec::new();
+    let mut masked = String::with_capacity(sql.len());
+    for token in tokenize(sql) {
+        if token.kind == TokenKind::Literal || token.kind == TokenKind::QuotedIdent {
+            masked += &format!(
+                "{}{}{}",
+                LITERAL_MARK_START,
+                literals.len(),
+                LITERAL_MARK_END
+            );
+            literals.push(token.text);
+        } else {
+            masked += token.text.as_str();
+        }
+    }
+    let rewritten = f(masked);
+    let mut result = String::with_capacity(rewritten.len());
+    let mut rest = rewritten.as_str();
+    while let Some(start) = rest.find(LITERAL_MARK_START) {
+        result += &rest[..start];
+        rest = &rest[start + LITERAL_MARK_START.len_utf8()..];
+        let end = rest.find(LITERAL_MARK_END).unwrap_or(rest.len());
+        if let Some(literal) = rest[..end]
+            .parse::<usize>()
+            .ok()
+            .and_then(|i| literals.get(i))
+        {
+            result += literal.as_str();
+        }
+        rest = &rest[cmp::min(end + LITERAL_MARK_END.len_utf8(), rest.len())..];
+    }
+    result += rest;
+    result
+}
+
+/// 用标记包裹改写出的占位符等，最终由 `take_marked_spans` 取出位置
+pub fn mark_span(text: &str) -> String {
+    format!("{}{}{}", SPAN_MARK_START, text, SPAN_MARK_END)
+}
+
+/// 不改写语句时，按词法单元标出其中的参数
+pub fn mark_params(sql: &str) -> String {
+    let mut result = String::with_capacity(sql.len());
+    for token in tokenize(sql) {
+        if token.kind == TokenKind::Param {
+            result += &mark_span(&token.text);
+        } else {
+            result += token.text.as_str();
+        }
+    }
+    result
+}
+
+/// 去掉 `mark_span` 加的标记，返回语句及被标记文本的字节区间，嵌套时只取最外层
+pub fn take_marked_spans(sql: &str) -> (String, Vec<ops::Range<usize>>) {
+    let mut result = String::with_capacity(sql.len());
+    let mut spans: Vec<ops::Range<usize>> = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for c in sql.chars() {
+        match c {
+            SPAN_MARK_START => {
+                if depth == 0 {
+                    start = result.len();
+                }
+                depth += 1;
+            }
+            SPAN_MARK_END if depth > 0 => {
+                depth -= 1;
+                if depth == 0 && start < result.len() {
+                    spans.push(start..result.len());
+                }
+            }
+            SPAN_MARK_END => {}
+            _ => result.push(c),
+        }
+    }
+    (result, spans)
+}
+
+/// 是否关键字，不区分大小写
+pub fn is_keyword(word: &str) -> bool {
+    let upper = word.to_ascii_uppercase();
+    KEYWORDS.contains(&upper.as_str())
+}
+
+fn next_token(chars: &[char], start: usize) -> (TokenKind, usize) {
+    let c = chars[start];
+    let next = chars.get(start + 1).copied();
+    if c.is_whitespace() {
+        return (
+            TokenKind::Whitespace,
+            scan_while(chars, start, |c| c.is_whitespace()),
+        );
+    } else if c == '\'' {
+        return (TokenKind::Literal, scan_quoted(chars, start, '\''));
+    } else if c == '"' || c == '`' {
+        return (TokenKind::QuotedIdent, scan_quoted(chars, start, c));
+    } else if c == '-' && next == Some('-') {
+        return (TokenKind::Comment, scan_while(chars, start, |c| c != '\n'));
+    } else if c == '/' && next == Some('*') {
+        return (TokenKind::Comment, scan_block_comment(chars, start));
+    } else if (c == '#' || c == '$') && next == Some('{') {
+        return (TokenKind::Param, scan_until(chars, start + 2, '}'));
+    } else if c == '#' || c == '$' {
+        let end = scan_while(chars, start + 1, |x| x != c && !x.is_whitespace());
+        if end < chars.len() && chars[end] == c && end > start + 1 {
+            return (TokenKind::Param, end + 1);
+        }
+        return (TokenKind::Symbol, start + 1);
+    } else if c.is_alphabetic() || c == '_' {
+        return (TokenKind::Word, scan_while(chars, start, is_word_char));
+    } else if c.is_ascii_digit() {
+        return (TokenKind::Symbol, scan_while(chars, start, is_word_char));
+    }
+    (TokenKind::Symbol, start + 1)
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '$' || c == '#'
+}
+
+fn scan_while(chars: &[char], start: usize, f: impl Fn(char) -> bool) -> usize {
+    let mut i = start;
+    while i < chars.len() && f(chars[i]) {
+        i += 1;
+    }
+    i
+}
+
+/// 扫描到 `close`（含），未闭合时到末尾
+fn scan_until(chars: &[char], start: usize, close: char) -> usize {
+    let end = scan_while(chars, start, |c| c != close);
+    cmp::min(end + 1, chars.len())
+}
+
+/// 扫描引号包围的内容，连续两个引号视为转义
+fn scan_quoted(chars: &[char], start: usize, quote: char) -> usize {
+    let mut i = start + 1;
+    while i < chars.len() {
+        if chars[i] == quote {
+            if chars.get(i + 1) == Some(&quote) {
+                i += 2;
+                continue;
+            }
+            return i + 1;
+        }
+        i += 1;
+    }
+    chars.len()
+}
+
+fn scan_block_comment(chars: &[char], start: usize) -> usize {
+    let mut i = start + 2;
+    while i + 1 < chars.len() {
+        if chars[i] == '*' && chars[i + 1] == '/' {
+            return i + 2;
+        }
+        i += 1;
+    }
+    chars.len()
+}
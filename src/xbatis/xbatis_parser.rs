@@ -1,78 +1,159 @@
 use super::def::*;
+use super::dtd_resolver::*;
 use super::parse_helper::*;
+use super::sql_analyzer::*;
+use super::sql_tokenizer::*;
+use crate::scan::archive_scanner::*;
+use crate::scan::text_decoder::*;
 use log::*;
-use regex::Regex;
+use regex::Captures;
 use std::collections::HashMap;
 use std::*;
 use xml::attribute::*;
+use xml::common::*;
 use xml::name::*;
 use xml::reader::*;
 
 /// 解析器
 pub trait Parser {
+    #[allow(dead_code)]
     fn setup_dialect_type(&mut self, dialect_type: DialectType);
 
-    fn parse(&self, files: &Vec<String>) -> Vec<String> {
-        let mut sql_store: Vec<String> = Vec::new();
-        for file in files {
-            self.check_and_parse(file, &mut sql_store);
-        }
-        return sql_store;
+    fn dialect_type(&self) -> &DialectType;
+
+    fn placeholder_style(&self) -> &PlaceholderStyle;
+
+    fn normalize_mode(&self) -> &NormalizeMode;
+
+    fn config(&self) -> &XBatisConfig;
+
+    /// 只保留本解析器能处理的文件，即 `iBATIS` 的 `sqlmap` 文件或 `MyBatis` 的 `mapper` 文件
+    fn retain_matched(&self, files: &mut Vec<String>) {
+        files.retain(|file| self.detect_match(file));
     }
 
-    fn check_and_parse(&self, file: &String, sql_store: &mut Vec<String>) {
-        if self.detect_match(file) {
+    /// 解析文件，文件应已经过 `retain_matched` 筛选
+    fn parse(&self, files: &Vec<String>) -> Vec<XmlParsedFile> {
+        let mut parsed_files: Vec<XmlParsedFile> = Vec::new();
+        for file in files {
             info!("try to parse [{}]", file);
-            self.read_and_parse(file, sql_store);
+            self.read_and_parse(file, &mut parsed_files);
         }
+        parsed_files
     }
 
-    fn detect_match(&self, file: &String) -> bool;
+    /// 按内容识别是否为本解析器能处理的文件
+    fn detect_content(&self, content: &str) -> bool;
 
-    fn detect_match_with_regex(&self, file: &String, re: &Regex) -> bool {
-        let result = fs::read_to_string(file);
-        if result.is_ok() {
-            return re.is_match(result.unwrap().as_str());
-        } else {
-            return false;
+    fn detect_match(&self, file: &str) -> bool {
+        match read_source(file) {
+            Ok(bytes) => self.detect_bytes(file, &bytes),
+            Err(_) => false,
         }
     }
 
-    fn read_and_parse(&self, file: &String, sql_store: &mut Vec<String>) {
-        self.read_xml(file, sql_store);
+    /// 按 `--encoding`、`BOM` 或 `xml` 声明解码后识别，扫描归档时据此决定缓存哪些条目
+    fn detect_bytes(&self, file: &str, bytes: &[u8]) -> bool {
+        self.detect_content(&decode(bytes, file, self.config().encoding))
+    }
+
+    fn read_and_parse(&self, file: &String, parsed_files: &mut Vec<XmlParsedFile>) {
+        self.read_xml(file, parsed_files);
     }
 
-    fn read_xml(&self, filename: &String, sql_store: &mut Vec<String>) {
-        sql_store.push("-- ".to_string() + filename);
-        let file = fs::File::open(filename).unwrap();
-        let buf = io::BufReader::new(file);
-        let parser = EventReader::new(buf);
+    fn read_xml(&self, filename: &String, parsed_files: &mut Vec<XmlParsedFile>) {
         let mut state = XmlParsedState::new();
         state.filename = filename.clone();
-        for e in parser {
+        let content = match read_source_to_string(filename, self.config().encoding) {
+            Ok(content) => content,
+            Err(e) => {
+                let error = XmlParseError {
+                    pos: SourcePos::default(),
+                    message: e.to_string(),
+                };
+                warn!("try to read {:?} failed: {}", filename, e);
+                parsed_files.push(XmlParsedFile {
+                    filename: state.filename,
+                    duplicates: Vec::new(),
+                    namespace: state.namespace,
+                    results: Vec::new(),
+                    sql_part_map: state.sql_part_map,
+                    error: Some(error),
+                });
+                return;
+            }
+        };
+        let content = resolve_doctype(&content, filename, self.config());
+        let mut parser = create_xml_reader(&content);
+        state.content = content.clone();
+        let mut error: Option<XmlParseError> = None;
+        loop {
+            let e = parser.next();
+            let pos = parser.position();
+            state.pos = SourcePos {
+                line: pos.row as u32 + 1,
+                column: pos.column as u32 + 1,
+            };
             match e {
                 Ok(XmlEvent::StartElement {
-                    name, attributes, ..
+                    name,
+                    mut attributes,
+                    ..
                 }) => {
+                    for attr in attributes.iter_mut() {
+                        attr.value = self.config().replace_properties(&attr.value);
+                    }
                     self.parse_start_element(name, attributes, &mut state);
                 }
                 Ok(XmlEvent::EndElement { name }) => {
                     self.parse_end_element(name, &mut state);
                 }
-                Ok(XmlEvent::CData(content)) => {
+                Ok(XmlEvent::CData(content)) | Ok(XmlEvent::Characters(content)) => {
+                    let content = self.config().replace_properties(&content);
                     self.fill_xml_content(&mut state, content);
                 }
-                Ok(XmlEvent::Characters(content)) => {
-                    self.fill_xml_content(&mut state, content);
+                Ok(XmlEvent::EndDocument) => {
+                    break;
                 }
                 Err(e) => {
-                    warn!("Error: {}", e);
+                    let pos = e.position();
+                    let parse_error = XmlParseError {
+                        pos: SourcePos {
+                            line: pos.row as u32 + 1,
+                            column: pos.column as u32 + 1,
+                        },
+                        message: e.msg().to_string(),
+                    };
+                    warn!(
+                        "try to parse failed, {}",
+                        parse_error.describe(&state.filename)
+                    );
+                    // 未读完的语句丢弃，只保留出错前完整的语句
+                    if state.in_statement {
+                        warn!("discard incomplete statement: {:?}", state.current_id);
+                        state.reset();
+                    }
+                    error = Some(parse_error);
                     break;
                 }
                 _ => {}
             }
         }
-        self.replace_and_fill(sql_store, &state.statements, &state.sql_part_map);
+        let mut results: Vec<SqlResult> = Vec::new();
+        self.replace_and_fill(
+            &mut results,
+            &state.filename,
+            &state.statements,
+            &state.sql_part_map,
+        );
+        parsed_files.push(XmlParsedFile {
+            filename: state.filename,
+            duplicates: Vec::new(),
+            namespace: state.namespace,
+            results,
+            sql_part_map: state.sql_part_map,
+            error,
+        });
     }
 
     fn fill_xml_content(&self, state: &mut XmlParsedState, content: String) {
@@ -103,14 +184,31 @@ pub trait Parser {
         state: &mut XmlParsedState,
     ) {
         let element_name = name.local_name.as_str().to_ascii_lowercase();
-        if match_statement(&element_name) {
+        if element_name == "mapper" || element_name == "sqlmap" {
+            search_matched_attr(&attributes, "namespace", |attr| {
+                state.namespace = attr.value.clone();
+            });
+        } else if match_statement(&element_name) {
             state.in_statement = true;
+            state.start_pos = state.pos;
             search_matched_attr(&attributes, "id", |attr| {
                 state.current_id = attr.value.clone();
             });
+            for attr in &attributes {
+                match attr.name.local_name.as_str() {
+                    "parameterType" | "parameterClass" => {
+                        state.parameter_type = self.config().resolve_type(&attr.value);
+                    }
+                    "resultType" | "resultClass" => {
+                        state.result_type = self.config().resolve_type(&attr.value);
+                    }
+                    _ => {}
+                }
+            }
         } else if element_name == "selectkey" {
             state.in_sql_key = true;
             state.has_sql_key = true;
+            state.key_start_pos = state.pos;
             state.current_key_id = state.current_id.as_str().to_string() + ".selectKey";
         } else if element_name == "where" {
             state.sql_builder += " where ";
@@ -121,6 +219,10 @@ pub trait Parser {
                 state.sql_builder += refid.as_str();
                 state.sql_builder += "_END__";
                 state.has_include = true;
+                state.include_sites.push(IncludeSite {
+                    refid: refid.clone(),
+                    pos: state.pos,
+                });
                 state.include_keys.push(refid);
             });
         } else {
@@ -131,8 +233,8 @@ pub trait Parser {
     fn ex_parse_start_element(
         &self,
         name: OwnedName,
-        element_name: &String,
-        attributes: &Vec<OwnedAttribute>,
+        element_name: &str,
+        attributes: &[OwnedAttribute],
         state: &mut XmlParsedState,
     );
 
@@ -146,20 +248,16 @@ pub trait Parser {
             }
         } else if element_name == "selectkey" {
             state.in_sql_key = false;
+            state.key_end_pos = state.pos;
         } else {
             self.ex_parse_end_element(name, &element_name, state);
         }
     }
 
-    fn ex_parse_end_element(
-        &self,
-        name: OwnedName,
-        element_name: &String,
-        state: &mut XmlParsedState,
-    );
+    fn ex_parse_end_element(&self, name: OwnedName, element_name: &str, state: &mut XmlParsedState);
 
     fn handle_end_sql_part(&self, mode: Mode, state: &mut XmlParsedState) {
-        let sql_stat = SqlStatement::new(
+        let mut sql_stat = SqlStatement::new(
             mode,
             state.current_id.clone(),
             state.sql_builder.to_string(),
@@ -168,6 +266,11 @@ pub trait Parser {
             false,
             SqlKey::empty(),
         );
+        sql_stat.span = SourceSpan {
+            start: state.start_pos,
+            end: state.pos,
+        };
+        sql_stat.xml = xml_fragment(&state.content, sql_stat.span);
         state
             .sql_part_map
             .insert(state.current_id.clone(), sql_stat);
@@ -175,7 +278,11 @@ pub trait Parser {
     }
 
     fn handle_end_statement(&self, mode: Mode, state: &mut XmlParsedState) {
-        let sql_stat = SqlStatement::new(
+        let key_span = SourceSpan {
+            start: state.key_start_pos,
+            end: state.key_end_pos,
+        };
+        let mut sql_stat = SqlStatement::new(
             mode,
             state.current_id.clone(),
             state.sql_builder.to_string(),
@@ -185,60 +292,156 @@ pub trait Parser {
             SqlKey {
                 key: state.current_key_id.clone(),
                 sql: state.key_sql_builder.to_string(),
+                xml: if state.has_sql_key {
+                    xml_fragment(&state.content, key_span)
+                } else {
+                    String::from("")
+                },
+                span: key_span,
             },
         );
+        sql_stat.span = SourceSpan {
+            start: state.start_pos,
+            end: state.pos,
+        };
+        sql_stat.xml = xml_fragment(&state.content, sql_stat.span);
+        sql_stat.include_sites = state.include_sites.clone();
+        sql_stat.parameter_type = state.parameter_type.clone();
+        sql_stat.result_type = state.result_type.clone();
         state.statements.push(sql_stat);
         state.reset();
     }
 
     fn replace_and_fill(
         &self,
-        sql_store: &mut Vec<String>,
+        results: &mut Vec<SqlResult>,
+        filename: &String,
         statements: &Vec<SqlStatement>,
         sql_part_map: &HashMap<String, SqlStatement>,
     ) {
         for stat in statements {
-            sql_store.push("--- ".to_string() + &stat.id);
+            let mut sql = stat.sql.clone();
             if stat.has_include {
-                let mut sql = stat.sql.clone();
                 for key in &stat.include_keys {
-                    let sql_part = sql_part_map.get_key_value(key).unwrap();
-                    sql = replace_included_sql(&sql, &sql_part.0, &sql_part.1.sql);
+                    match sql_part_map.get(key) {
+                        Some(sql_part) => sql = replace_included_sql(&sql, key, &sql_part.sql),
+                        // 未声明或声明在解析出错之后，保留 `__INCLUDE_ID_..._END__` 标记
+                        None => warn!(
+                            "can not resolve include {:?} of {:?} in {:?}, keep it as a marker",
+                            key,
+                            stat.id,
+                            stat.span.start.location(filename)
+                        ),
+                    }
                 }
-                self.clear_and_push(sql_store, &sql);
-            } else {
-                self.clear_and_push(sql_store, &stat.sql);
             }
+            let mut result = self.build_result(stat.mode, &stat.id, &sql, &stat.xml);
+            result.span = stat.span;
+            result.include_sites = stat.include_sites.clone();
+            result.parameter_type = stat.parameter_type.clone();
+            result.result_type = stat.result_type.clone();
+            result.result_mappings = self
+                .config()
+                .result_mappings(&result.result_type, &select_labels(&result.sql));
+            results.push(result);
             if stat.has_sql_key {
-                sql_store.push("--- ".to_string() + &stat.sql_key.key);
-                self.clear_and_push(sql_store, &stat.sql_key.sql);
+                let mut result = self.build_result(
+                    Mode::SelectKey,
+                    &stat.sql_key.key,
+                    &stat.sql_key.sql,
+                    &stat.sql_key.xml,
+                );
+                result.span = stat.sql_key.span;
+                results.push(result);
             }
         }
     }
 
-    fn clear_and_push(&self, sql_store: &mut Vec<String>, origin_sql: &String);
-
-    fn loop_clear_and_push(
-        &self,
-        sql_store: &mut Vec<String>,
-        regex_replacements: &Vec<RegexReplacement>,
-        origin_sql: &String,
-    ) {
-        let mut sql = String::from(origin_sql.to_ascii_uppercase().trim());
-        for regex_replacement in regex_replacements.iter() {
-            sql = self.regex_clear_and_push(&sql, regex_replacement);
+    /// 整理语句，收集参数与涉及的表
+    fn build_result(&self, mode: Mode, id: &str, origin_sql: &str, xml: &str) -> SqlResult {
+        let (sql, marked_spans) = take_marked_spans(&self.clear(origin_sql));
+        let (table_refs, unresolved_columns) = analyze_tables(&sql);
+        SqlResult {
+            mode,
+            id: id.to_string(),
+            params: self.collect_params(origin_sql),
+            substitutions: self.collect_substitutions(origin_sql),
+            table_refs,
+            unresolved_columns,
+            sql,
+            marked_spans,
+            raw_sql: origin_sql.trim().to_string(),
+            xml: xml.to_string(),
+            span: SourceSpan::default(),
+            include_sites: Vec::new(),
+            parameter_type: String::from(""),
+            result_type: String::from(""),
+            result_mappings: Vec::new(),
         }
-        sql_store.push(sql + ";");
+    }
+
+    fn collect_params(&self, origin_sql: &str) -> Vec<SqlParam>;
+
+    /// 收集直接拼接的文本替换，`MyBatis` 的 `${...}` / `iBATIS` 的 `$...$`
+    fn collect_substitutions(&self, origin_sql: &str) -> Vec<String>;
+
+    fn clear(&self, origin_sql: &str) -> String;
+
+    fn loop_clear(&self, regex_replacements: &[RegexReplacement], origin_sql: &str) -> String {
+        let sql = match self.normalize_mode() {
+            NormalizeMode::None => {
+                return mark_params(origin_sql.trim());
+            }
+            NormalizeMode::Whitespace => {
+                return mark_params(collapse_whitespace(origin_sql).trim());
+            }
+            NormalizeMode::Full => {
+                requote_identifiers(&uppercase_keywords(origin_sql.trim()), |name| {
+                    self.dialect_type().quote_identifier(name)
+                })
+            }
+        };
+        // 占位符、文本替换等只改写字符串之外的部分
+        rewrite_outside_literals(collapse_whitespace(&sql).trim(), |mut sql| {
+            for regex_replacement in regex_replacements.iter() {
+                sql = self.regex_clear_and_push(&sql, regex_replacement);
+            }
+            sql
+        })
     }
 
     fn regex_clear_and_push(
         &self,
-        origin_sql: &String,
+        origin_sql: &str,
         regex_replacement: &RegexReplacement,
     ) -> String {
-        return regex_replacement
+        if regex_replacement.is_placeholder {
+            let mut index = 0;
+            return regex_replacement
+                .regex
+                .replace_all(origin_sql, |caps: &Captures| {
+                    index += 1;
+                    mark_span(&render_placeholder(
+                        self.placeholder_style(),
+                        index,
+                        &caps[0],
+                    ))
+                })
+                .to_string();
+        }
+        if regex_replacement.is_marked {
+            return regex_replacement
+                .regex
+                .replace_all(origin_sql, |caps: &Captures| {
+                    let mut text = String::new();
+                    caps.expand(&regex_replacement.target, &mut text);
+                    mark_span(&text)
+                })
+                .to_string();
+        }
+        regex_replacement
             .regex
             .replace_all(origin_sql, regex_replacement.target.as_str())
-            .to_string();
+            .to_string()
     }
 }